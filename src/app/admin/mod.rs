@@ -0,0 +1,10 @@
+mod role;
+
+use axum::{routing::patch, Router};
+use role::set_role;
+
+use super::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/role/:user_id", patch(set_role))
+}
@@ -0,0 +1,72 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use mongodb::{
+    bson::{doc, to_bson},
+    Database,
+};
+
+use crate::app::{
+    auth::{middleware::Auth, role::require_role},
+    models::{
+        dto::user::{RolePath, SetRolePayload},
+        Role, User,
+    },
+};
+
+/// Changes a user's role. Only admins can do this.
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn set_role(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<RolePath>,
+    Json(payload): Json<SetRolePayload>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to change a user's role"),
+        )
+            .into_response();
+    };
+
+    if let Err(response) = require_role(&db, auth.id, &[Role::Admin]).await {
+        return response;
+    }
+    // Stops the last admin from locking everyone out
+    if path.user_id == auth.id && payload.role != Role::Admin {
+        return (
+            StatusCode::BAD_REQUEST,
+            String::from("You cannot remove your own admin role"),
+        )
+            .into_response();
+    }
+
+    let Ok(role) = to_bson(&payload.role) else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let result = db
+        .collection::<User>("users")
+        .update_one(
+            doc! { "_id": path.user_id },
+            doc! { "$set": {
+                "role": role,
+                "is_patient": payload.role == Role::Patient
+            } },
+        )
+        .await;
+    match result {
+        Ok(result) if result.matched_count == 0 => {
+            (StatusCode::NOT_FOUND, String::from("Could not find user")).into_response()
+        }
+        Ok(..) => StatusCode::OK.into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
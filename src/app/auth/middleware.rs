@@ -7,7 +7,10 @@ use mongodb::bson::oid::ObjectId;
 use tower_cookies::Cookies;
 use tracing::info;
 
-use crate::app::{auth::utils::decode_jwt, models::User};
+use crate::app::{
+    auth::utils::decode_jwt,
+    models::{Role, User},
+};
 
 #[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
 pub struct UserClaims {
@@ -18,17 +21,35 @@ pub struct UserClaims {
     // national_health_identifer: String,
     pub email_address: String,
     pub is_patient: bool,
+    /// Tokens issued before roles were added don't have one, see [`UserClaims::role`]
+    #[serde(default)]
+    pub role: Option<Role>,
+    /// The session the token was issued for. Tokens from before sessions were added don't have
+    /// one and are no longer accepted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<ObjectId>,
+}
+
+impl UserClaims {
+    #[must_use]
+    pub fn role(&self) -> Role {
+        self.role
+            .unwrap_or_else(|| Role::from_is_patient(self.is_patient))
+    }
 }
 
 impl TryInto<UserClaims> for User {
     type Error = anyhow::Error;
     fn try_into(self) -> Result<UserClaims, Self::Error> {
+        let role = self.role();
         Ok(UserClaims {
             id: self.id.clone().ok_or(anyhow!("user is missing id"))?,
             first_name: self.first_name,
             last_name: self.last_name,
             email_address: self.email_address,
             is_patient: self.is_patient,
+            role: Some(role),
+            session_id: None,
         })
     }
 }
@@ -52,6 +73,10 @@ where
             return Ok(Auth(None));
         };
         match decode_jwt(auth_cookie.value()) {
+            Ok(value) if value.user.session_id.is_none() => {
+                info!("Token was issued before sessions were added");
+                Ok(Auth(None))
+            }
             Ok(value) => Ok(Auth(Some(value.user))),
             Err(err) => {
                 info!(
@@ -1,12 +1,16 @@
+pub mod keys;
 pub mod middleware;
+pub mod password;
+pub mod role;
+pub mod session;
 pub mod utils;
 
 use argon2::{self, Config};
 use axum::{
     extract::State,
-    http::StatusCode,
+    http::{header::USER_AGENT, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
-    routing::{get, patch, post},
+    routing::{delete, get, patch, post},
     Json, Router,
 };
 use mongodb::{
@@ -21,7 +25,7 @@ use tracing::info;
 
 use crate::{
     app::auth::middleware::Auth,
-    app::auth::utils::{generate_jwt, AuthCookieBuilder},
+    app::auth::session::{clear_session_cookies, end_session, start_session},
     app::models::User,
     app::AppState,
 };
@@ -45,6 +49,14 @@ pub fn router() -> Router<AppState> {
         .route("/create", post(create))
         .route("/login", post(login))
         .route("/logout", patch(logout))
+        .route("/refresh", post(session::refresh))
+        .route("/forgot-password", post(password::forgot_password))
+        .route("/reset-password", post(password::reset_password))
+        .route(
+            "/sessions",
+            get(session::find_sessions).delete(session::revoke_all_sessions),
+        )
+        .route("/sessions/:session_id", delete(session::revoke_session))
         .route("/info", get(info))
 }
 
@@ -128,6 +140,7 @@ pub struct LoginUserBody {
 
 pub async fn login(
     cookies: Cookies,
+    headers: HeaderMap,
     State(state): State<AppState>,
     Json(body): Json<LoginUserBody>,
 ) -> Response {
@@ -152,7 +165,13 @@ pub async fn login(
         user_without_password
     );
 
-    let Ok(cookie_value) = generate_jwt(&user_without_password) else {
+    let user_agent = headers
+        .get(USER_AGENT)
+        .and_then(|user_agent| user_agent.to_str().ok())
+        .map(String::from);
+    let Ok(user_with_session) =
+        start_session(&state.db, &cookies, user_without_password, user_agent).await
+    else {
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
             String::from("couldn't generate a cookie"),
@@ -160,20 +179,16 @@ pub async fn login(
             .into_response();
     };
 
-    let auth_cookie = AuthCookieBuilder::new(cookie_value)
-        .max_age(tower_cookies::cookie::time::Duration::weeks(2))
-        .build();
-
-    info!("{:#?}", auth_cookie);
-    cookies.add(auth_cookie);
-
-    (StatusCode::OK, Json(user_without_password)).into_response()
-
+    (StatusCode::OK, Json(user_with_session)).into_response()
 }
 
-async fn logout(cookies: Cookies) -> Response {
-    let mut auth_cookie = AuthCookieBuilder::new(String::new()).build();
-    auth_cookie.make_removal();
-    cookies.add(auth_cookie);
+async fn logout(cookies: Cookies, State(state): State<AppState>) -> Response {
+    if let Some(refresh_cookie) = cookies.get("refresh_token") {
+        if let Err(e) = end_session(&state.db, refresh_cookie.value()).await {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    }
+    clear_session_cookies(&cookies);
     (StatusCode::OK).into_response()
 }
@@ -0,0 +1,216 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Duration, Utc};
+use mongodb::{
+    bson::{doc, oid::ObjectId, DateTime},
+    Database,
+};
+use tower_cookies::Cookies;
+
+use super::{
+    hash_password,
+    session::{clear_session_cookies, hash_token},
+};
+use crate::{
+    app::{
+        email::{Email, EmailSender},
+        models::{
+            dto::user::{ForgotPasswordPayload, ResetPasswordPayload},
+            generate_random_string, PasswordResetToken, Session, User,
+        },
+    },
+    config,
+};
+
+/// How long a password reset link works for
+const RESET_TOKEN_MINUTES: i64 = 60;
+
+const RESET_TOKEN_LENGTH: usize = 48;
+
+/// How long to wait before sending another reset link to the same account, so that the endpoint
+/// can't be used to flood someone's inbox
+const RESET_EMAIL_INTERVAL_MINUTES: i64 = 5;
+
+/// Emails a password reset link to the account with the given email address
+///
+/// The response is the same whether or not there is such an account, and the work is done after
+/// responding, so that this can't be used to find out who has an account. Each account is sent at
+/// most one link every `RESET_EMAIL_INTERVAL_MINUTES` minutes.
+#[tracing::instrument(skip(payload))]
+#[axum::debug_handler(state = crate::app::AppState)]
+pub async fn forgot_password(
+    State(db): State<Database>,
+    State(email): State<Arc<dyn EmailSender>>,
+    Json(payload): Json<ForgotPasswordPayload>,
+) -> Response {
+    tokio::spawn(async move {
+        if let Err(e) = send_reset_link(&db, email.as_ref(), payload.email_address).await {
+            tracing::error!(error = %e, "Failed to send password reset link");
+        }
+    });
+    (
+        StatusCode::ACCEPTED,
+        String::from("If an account uses that email address, a reset link has been sent to it"),
+    )
+        .into_response()
+}
+
+async fn send_reset_link(
+    db: &Database,
+    email: &dyn EmailSender,
+    email_address: String,
+) -> anyhow::Result<()> {
+    let Some(user) = db
+        .collection::<User>("users")
+        .find_one(doc! { "email_address": &email_address })
+        .await?
+    else {
+        return Ok(());
+    };
+    let user_id = user.id.context("User has no id")?;
+
+    let tokens = db.collection::<PasswordResetToken>("password_reset_tokens");
+    let now = Utc::now();
+    let recently_sent = tokens
+        .find_one(doc! {
+          "user_id": user_id,
+          "created_at": {
+            "$gt": DateTime::from_chrono(now - Duration::minutes(RESET_EMAIL_INTERVAL_MINUTES))
+          }
+        })
+        .await?;
+    if recently_sent.is_some() {
+        tracing::info!(%user_id, "Not sending another password reset link so soon");
+        return Ok(());
+    }
+
+    // Only the newest link works
+    tokens
+        .delete_many(doc! { "user_id": user_id, "used_at": null })
+        .await?;
+
+    let token = generate_random_string(RESET_TOKEN_LENGTH);
+    tokens
+        .insert_one(PasswordResetToken {
+            id: ObjectId::new(),
+            user_id,
+            token_hash: hash_token(&token),
+            created_at: DateTime::from_chrono(now),
+            expires_at: DateTime::from_chrono(now + Duration::minutes(RESET_TOKEN_MINUTES)),
+            used_at: None,
+        })
+        .await?;
+
+    let link = format!("{}/{token}", config::get_reset_password_link_base());
+    email
+        .send(Email {
+            to: email_address,
+            subject: String::from("Reset your Parkinson's Pulse password"),
+            body: format!(
+                "Hi {},\n\nSomeone asked to reset the password for your account. To choose a new \
+                 password, open this link within {RESET_TOKEN_MINUTES} minutes:\n\n{link}\n\n\
+                 If this wasn't you, you can ignore this email and your password won't change.",
+                user.first_name
+            ),
+        })
+        .await
+}
+
+/// Sets a new password using the token from a reset link, and signs the user out everywhere
+#[tracing::instrument(skip(cookies, payload))]
+#[axum::debug_handler(state = crate::app::AppState)]
+pub async fn reset_password(
+    cookies: Cookies,
+    State(db): State<Database>,
+    State(email): State<Arc<dyn EmailSender>>,
+    Json(payload): Json<ResetPasswordPayload>,
+) -> Response {
+    let now = DateTime::now();
+    let token = match db
+        .collection::<PasswordResetToken>("password_reset_tokens")
+        .find_one_and_update(
+            doc! {
+              "token_hash": hash_token(&payload.token),
+              "used_at": null,
+              "expires_at": { "$gt": now }
+            },
+            doc! { "$set": { "used_at": now } },
+        )
+        .await
+    {
+        Ok(Some(token)) => token,
+        Ok(None) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                String::from("This reset link is invalid or has expired"),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let Ok(hashed_password) = hash_password(&payload.password) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            String::from("failed to hash password"),
+        )
+            .into_response();
+    };
+    let user = match db
+        .collection::<User>("users")
+        .find_one_and_update(
+            doc! { "_id": token.user_id },
+            doc! { "$set": { "hashed_password": hashed_password } },
+        )
+        .await
+    {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, String::from("Could not find user")).into_response();
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    // Whoever knew the old password may still be signed in
+    if let Err(e) = db
+        .collection::<Session>("sessions")
+        .delete_many(doc! { "user_id": token.user_id })
+        .await
+    {
+        tracing::error!(error = %e, "Error occurred while querying database");
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+    clear_session_cookies(&cookies);
+
+    tokio::spawn(async move {
+        let result = email
+            .send(Email {
+                to: user.email_address,
+                subject: String::from("Your Parkinson's Pulse password was changed"),
+                body: format!(
+                    "Hi {},\n\nThe password for your account was just changed, and you have been \
+                     signed out on every device. If this wasn't you, please contact us straight \
+                     away.",
+                    user.first_name
+                ),
+            })
+            .await;
+        if let Err(e) = result {
+            tracing::error!(error = %e, "Failed to send password changed email");
+        }
+    });
+    StatusCode::OK.into_response()
+}
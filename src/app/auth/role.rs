@@ -0,0 +1,41 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use mongodb::{
+    bson::{doc, oid::ObjectId},
+    Database,
+};
+
+use crate::app::models::{Role, User};
+
+/// Finds a user and checks that they have one of `roles`
+///
+/// This reads the role from the database rather than the user's token, so a role that has been
+/// taken away stops working straight away.
+///
+/// # Errors
+/// Returns the response that should be sent back if the user doesn't have the role or the
+/// database could not be queried
+pub async fn require_role(
+    db: &Database,
+    user_id: ObjectId,
+    roles: &[Role],
+) -> Result<User, Response> {
+    match db
+        .collection::<User>("users")
+        .find_one(doc! { "_id": user_id })
+        .await
+    {
+        Ok(Some(user)) if roles.contains(&user.role()) => Ok(user),
+        Ok(..) => Err((
+            StatusCode::FORBIDDEN,
+            format!("Only users with one of these roles can do this: {roles:?}"),
+        )
+            .into_response()),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Duration, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, DateTime},
+    Database,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tower_cookies::Cookies;
+
+use super::{
+    middleware::{Auth, UserClaims},
+    utils::{generate_jwt, AuthCookieBuilder, ACCESS_TOKEN_MINUTES},
+};
+use crate::app::models::{dto::user::SessionPath, generate_random_string, Session, User};
+
+/// How long someone stays signed in on a device without using it
+const SESSION_DAYS: i64 = 14;
+
+const REFRESH_TOKEN_LENGTH: usize = 48;
+
+/// How often live updates check that the session they were opened with is still going
+const SESSION_CHECK_SECONDS: u64 = 60;
+
+pub(super) fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Sets the access and refresh token cookies for a session
+fn set_session_cookies(
+    cookies: &Cookies,
+    user: &UserClaims,
+    refresh_token: String,
+) -> anyhow::Result<()> {
+    let access_token = generate_jwt(user)?;
+    cookies.add(
+        AuthCookieBuilder::new(access_token)
+            .max_age(tower_cookies::cookie::time::Duration::minutes(
+                ACCESS_TOKEN_MINUTES,
+            ))
+            .build(),
+    );
+    cookies.add(
+        AuthCookieBuilder::refresh(refresh_token)
+            .max_age(tower_cookies::cookie::time::Duration::days(SESSION_DAYS))
+            .build(),
+    );
+    Ok(())
+}
+
+/// Removes both session cookies
+pub(super) fn clear_session_cookies(cookies: &Cookies) {
+    let mut auth_cookie = AuthCookieBuilder::new(String::new()).build();
+    auth_cookie.make_removal();
+    cookies.add(auth_cookie);
+    let mut refresh_cookie = AuthCookieBuilder::refresh(String::new()).build();
+    refresh_cookie.make_removal();
+    cookies.add(refresh_cookie);
+}
+
+/// Starts a session for a user who has just signed in and sets the cookies for it
+///
+/// Returns the user's claims with the session added
+pub(super) async fn start_session(
+    db: &Database,
+    cookies: &Cookies,
+    mut user: UserClaims,
+    user_agent: Option<String>,
+) -> anyhow::Result<UserClaims> {
+    let refresh_token = generate_random_string(REFRESH_TOKEN_LENGTH);
+    let now = Utc::now();
+    let session = Session {
+        id: ObjectId::new(),
+        user_id: user.id,
+        refresh_token_hash: hash_token(&refresh_token),
+        previous_refresh_token_hash: None,
+        user_agent,
+        created_at: DateTime::from_chrono(now),
+        last_used_at: DateTime::from_chrono(now),
+        expires_at: DateTime::from_chrono(now + Duration::days(SESSION_DAYS)),
+    };
+    db.collection::<Session>("sessions")
+        .insert_one(&session)
+        .await?;
+
+    user.session_id = Some(session.id);
+    set_session_cookies(cookies, &user, refresh_token)?;
+    Ok(user)
+}
+
+/// Ends the session that a refresh token belongs to, if there is one
+pub(super) async fn end_session(db: &Database, refresh_token: &str) -> mongodb::error::Result<()> {
+    db.collection::<Session>("sessions")
+        .delete_one(doc! { "refresh_token_hash": hash_token(refresh_token) })
+        .await?;
+    Ok(())
+}
+
+/// Swaps the refresh token cookie for a new one, along with a new access token
+///
+/// Using a refresh token that has already been swapped ends its session, since it means someone
+/// else may have a copy of it.
+#[tracing::instrument(skip(cookies))]
+#[axum::debug_handler]
+pub async fn refresh(cookies: Cookies, State(db): State<Database>) -> Response {
+    let Some(refresh_cookie) = cookies.get("refresh_token") else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must sign in again"),
+        )
+            .into_response();
+    };
+    let token_hash = hash_token(refresh_cookie.value());
+    let refresh_token = generate_random_string(REFRESH_TOKEN_LENGTH);
+    let now = Utc::now();
+
+    let sessions = db.collection::<Session>("sessions");
+    let session = match sessions
+        .find_one_and_update(
+            doc! {
+              "refresh_token_hash": &token_hash,
+              "expires_at": { "$gt": DateTime::from_chrono(now) }
+            },
+            doc! { "$set": {
+              "refresh_token_hash": hash_token(&refresh_token),
+              "previous_refresh_token_hash": &token_hash,
+              "last_used_at": DateTime::from_chrono(now),
+              "expires_at": DateTime::from_chrono(now + Duration::days(SESSION_DAYS))
+            } },
+        )
+        .await
+    {
+        Ok(Some(session)) => session,
+        Ok(None) => {
+            match sessions
+                .delete_one(doc! { "previous_refresh_token_hash": &token_hash })
+                .await
+            {
+                Ok(result) if result.deleted_count > 0 => {
+                    tracing::warn!("Refresh token was used twice, so its session was ended");
+                }
+                Ok(..) => {}
+                Err(e) => {
+                    tracing::error!(error = %e, "Error occurred while querying database");
+                    return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                }
+            }
+            clear_session_cookies(&cookies);
+            return (
+                StatusCode::UNAUTHORIZED,
+                String::from("You must sign in again"),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    // Read the user again so that changes to their name or role show up in the new token
+    let user = match db
+        .collection::<User>("users")
+        .find_one(doc! { "_id": session.user_id })
+        .await
+    {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            clear_session_cookies(&cookies);
+            return (
+                StatusCode::UNAUTHORIZED,
+                String::from("Could not find user"),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let Ok(mut claims): Result<UserClaims, _> = user.try_into() else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    claims.session_id = Some(session.id);
+
+    if let Err(e) = set_session_cookies(&cookies, &claims, refresh_token) {
+        tracing::error!(error = %e, "Failed to generate access token");
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+    (StatusCode::OK, Json(claims)).into_response()
+}
+
+#[derive(Serialize, Deserialize)]
+struct SessionInfo {
+    #[serde(rename = "_id")]
+    id: ObjectId,
+    user_agent: Option<String>,
+    created_at: DateTime,
+    last_used_at: DateTime,
+    expires_at: DateTime,
+    /// Whether this is the session the request was made with
+    current: bool,
+}
+
+/// Lists the devices the signed-in user is signed in on
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn find_sessions(State(db): State<Database>, Auth(auth): Auth) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to view your sessions"),
+        )
+            .into_response();
+    };
+
+    let result = db
+        .collection::<Session>("sessions")
+        .find(doc! {
+          "user_id": auth.id,
+          "expires_at": { "$gt": DateTime::now() }
+        })
+        .sort(doc! { "last_used_at": -1 })
+        .await;
+    let sessions = match result {
+        Ok(cursor) => cursor.try_collect::<Vec<Session>>().await,
+        Err(e) => Err(e),
+    };
+    match sessions {
+        Ok(sessions) => {
+            let sessions = sessions
+                .into_iter()
+                .map(|session| SessionInfo {
+                    current: auth.session_id == Some(session.id),
+                    id: session.id,
+                    user_agent: session.user_agent,
+                    created_at: session.created_at,
+                    last_used_at: session.last_used_at,
+                    expires_at: session.expires_at,
+                })
+                .collect::<Vec<_>>();
+            (StatusCode::OK, Json(sessions)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Completes once a session has been ended or has run out, so that long-lived connections opened
+/// with it can be closed. It is checked every `SESSION_CHECK_SECONDS` seconds.
+pub async fn session_ended(db: Database, session_id: Option<ObjectId>) {
+    let Some(session_id) = session_id else {
+        return;
+    };
+    let sessions = db.collection::<Session>("sessions");
+    let mut checks = tokio::time::interval(std::time::Duration::from_secs(SESSION_CHECK_SECONDS));
+    loop {
+        checks.tick().await;
+        match sessions
+            .find_one(doc! { "_id": session_id, "expires_at": { "$gt": DateTime::now() } })
+            .await
+        {
+            Ok(Some(..)) => {}
+            Ok(None) => return,
+            Err(e) => {
+                tracing::error!(error = %e, "Error occurred while querying database");
+                return;
+            }
+        }
+    }
+}
+
+/// Signs the user out on one device. Its access token keeps working until it expires, which is
+/// at most `ACCESS_TOKEN_MINUTES` minutes, but live updates it opened stop within
+/// `SESSION_CHECK_SECONDS` seconds.
+#[tracing::instrument(skip(cookies))]
+#[axum::debug_handler]
+pub async fn revoke_session(
+    cookies: Cookies,
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<SessionPath>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to end a session"),
+        )
+            .into_response();
+    };
+
+    let result = db
+        .collection::<Session>("sessions")
+        .delete_one(doc! { "_id": path.session_id, "user_id": auth.id })
+        .await;
+    match result {
+        Ok(result) if result.deleted_count == 0 => (
+            StatusCode::NOT_FOUND,
+            String::from("Could not find session"),
+        )
+            .into_response(),
+        Ok(..) => {
+            if auth.session_id == Some(path.session_id) {
+                clear_session_cookies(&cookies);
+            }
+            StatusCode::OK.into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Signs the user out on every device, including this one
+#[tracing::instrument(skip(cookies))]
+#[axum::debug_handler]
+pub async fn revoke_all_sessions(
+    cookies: Cookies,
+    State(db): State<Database>,
+    Auth(auth): Auth,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to end your sessions"),
+        )
+            .into_response();
+    };
+
+    match db
+        .collection::<Session>("sessions")
+        .delete_many(doc! { "user_id": auth.id })
+        .await
+    {
+        Ok(..) => {
+            clear_session_cookies(&cookies);
+            StatusCode::OK.into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
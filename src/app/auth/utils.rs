@@ -1,13 +1,19 @@
-use crate::{app::auth::middleware::UserClaims, config};
+use crate::{
+    app::auth::{keys::keyset, middleware::UserClaims},
+    config,
+};
 use anyhow::anyhow;
 use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
 use tower_cookies::{
     cookie::{time::OffsetDateTime, CookieBuilder},
     Cookie,
 };
 
+/// How long access tokens last. They can't be revoked, so they are kept short and renewed with
+/// the session's refresh token.
+pub const ACCESS_TOKEN_MINUTES: i64 = 15;
+
 #[derive(Serialize, Deserialize)]
 pub struct Claims {
     pub user: UserClaims,
@@ -19,12 +25,21 @@ pub struct AuthCookieBuilder<'a> {
 }
 
 impl<'a> AuthCookieBuilder<'a> {
+    /// Cookie holding the access token
     #[must_use]
     pub fn new(value: String) -> Self {
+        Self::with_name("auth_token", value, "/")
+    }
+    /// Cookie holding the refresh token, which is only sent to the auth routes
+    #[must_use]
+    pub fn refresh(value: String) -> Self {
+        Self::with_name("refresh_token", value, "/auth")
+    }
+    fn with_name(name: &'a str, value: String, path: &'a str) -> Self {
         Self {
-            cookie: CookieBuilder::new("auth_token", value)
+            cookie: CookieBuilder::new(name, value)
                 .domain(config::get_domain())
-                .path("/")
+                .path(path)
                 .http_only(true)
                 .same_site(tower_cookies::cookie::SameSite::None)
                 .secure(config::get_is_production() == "PRODUCTION"),
@@ -53,32 +68,21 @@ impl<'a> AuthCookieBuilder<'a> {
 ///
 pub fn generate_jwt(user: &UserClaims) -> anyhow::Result<String> {
     let exp = Utc::now()
-        .checked_add_signed(Duration::weeks(3))
+        .checked_add_signed(Duration::minutes(ACCESS_TOKEN_MINUTES))
         .ok_or(anyhow!("couldn't add signed"))?
         .timestamp();
     let claims = Claims {
         user: user.clone(),
         exp: usize::try_from(exp)?,
     };
-    let jwt_secret = config::get_jwt_secret();
-    Ok(encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(jwt_secret.as_ref()),
-    )?)
+    keyset().sign(&claims)
 }
 
 ///
 ///
 /// # Errors
-/// This function errors if the JWT token is invalid
+/// This function errors if the JWT token is invalid or was signed with a key that is no longer
+/// trusted
 pub fn decode_jwt(token: &str) -> anyhow::Result<Claims> {
-    let jwt_secret = config::get_jwt_secret();
-    let validation = Validation::new(Algorithm::HS256);
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(jwt_secret.as_ref()),
-        &validation,
-    )?;
-    Ok(token_data.claims)
+    keyset().verify(token, Utc::now())
 }
@@ -0,0 +1,159 @@
+use axum::{
+    extract::{Path, State},
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{Datelike, NaiveDate, Utc, Weekday};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Bson},
+    Database,
+};
+
+use crate::app::{
+    calendar::ics::{render, CalendarEvent, Recurrence},
+    medication::find::get_users_medications,
+    models::{dto::calendar::FeedPath, Form, MedicationTrackerEntry, User},
+};
+
+const ALARM_MINUTES_BEFORE: u32 = 10;
+
+/// The first day on or after `date` that falls on one of `days`, or `date` if every day is
+/// scheduled
+///
+/// Calendar apps count `DTSTART` as an occurrence even if it isn't one of the `BYDAY` days, so
+/// weekly events have to start on one of them.
+fn first_scheduled_day(date: NaiveDate, days: &[Weekday]) -> NaiveDate {
+    date.iter_days()
+        .take(7)
+        .find(|day| days.contains(&day.weekday()))
+        .unwrap_or(date)
+}
+
+fn medication_events(medication: &MedicationTrackerEntry) -> Vec<CalendarEvent> {
+    let Some(schedule) = &medication.schedule else {
+        return Vec::new();
+    };
+    let starts_on = schedule
+        .starts_on
+        .unwrap_or_else(|| medication.id.timestamp().to_chrono().date_naive());
+    let recurrence = if schedule.days.is_empty() {
+        Recurrence::Daily { interval: 1 }
+    } else {
+        Recurrence::Weekly {
+            days: schedule.days.clone(),
+        }
+    };
+    let starts_on = first_scheduled_day(starts_on, &schedule.days);
+
+    schedule
+        .times
+        .iter()
+        .map(|time| CalendarEvent {
+            uid: format!(
+                "medication-{}-{}@parkinsons-pulse",
+                medication.id,
+                time.format("%H%M")
+            ),
+            summary: format!("Take {} ({})", medication.medication_name, medication.dose),
+            description: Some(medication.timing.clone()),
+            starts_at: starts_on.and_time(*time).and_utc(),
+            recurrence: recurrence.clone(),
+            until: schedule.ends_on,
+            alarm_minutes_before: ALARM_MINUTES_BEFORE,
+        })
+        .collect()
+}
+
+fn form_event(form: &Form) -> Option<CalendarEvent> {
+    let schedule = form.schedule.as_ref()?;
+    Some(CalendarEvent {
+        uid: format!("form-{}@parkinsons-pulse", form.id?),
+        summary: format!("Fill in {}", form.title),
+        description: form.description.clone(),
+        starts_at: schedule.starts_on.and_time(schedule.time).and_utc(),
+        recurrence: Recurrence::Daily {
+            interval: schedule.every_days,
+        },
+        until: None,
+        alarm_minutes_before: ALARM_MINUTES_BEFORE,
+    })
+}
+
+/// Serves a user's medication and form schedules as an iCalendar feed. This is not behind
+/// authentication as calendar apps can't sign in, so the secret in the URL is what identifies
+/// the user.
+#[tracing::instrument(skip(path))]
+#[axum::debug_handler]
+pub async fn feed(State(db): State<Database>, Path(path): Path<FeedPath>) -> Response {
+    let Some(calendar_token) = path.feed.strip_suffix(".ics") else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let user = match db
+        .collection::<User>("users")
+        .find_one(doc! { "calendar_token": calendar_token })
+        .await
+    {
+        Ok(Some(user)) => user,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let Some(user_id) = user.id else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+
+    let Ok(medications) = get_users_medications(user_id, false, &db).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let forms = match db
+        .collection::<Form>("forms")
+        .find(doc! { "user_id": user_id, "schedule": { "$ne": Bson::Null } })
+        .await
+    {
+        Ok(cursor) => match cursor.try_collect::<Vec<Form>>().await {
+            Ok(forms) => forms,
+            Err(e) => {
+                tracing::error!(error = %e, "Error occurred while reading forms");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        },
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let events = medications
+        .iter()
+        .flat_map(medication_events)
+        .chain(forms.iter().filter_map(form_event))
+        .collect::<Vec<_>>();
+    let calendar = render("Parkinson's Pulse", &events, Utc::now());
+
+    (
+        StatusCode::OK,
+        [(CONTENT_TYPE, "text/calendar; charset=utf-8")],
+        calendar,
+    )
+        .into_response()
+}
+
+#[test]
+fn weekly_reminders_start_on_a_scheduled_day() {
+    // 3 June 2024 was a Monday
+    let monday = NaiveDate::from_ymd_opt(2024, 6, 3).unwrap();
+    assert_eq!(
+        first_scheduled_day(monday, &[Weekday::Wed, Weekday::Fri]),
+        NaiveDate::from_ymd_opt(2024, 6, 5).unwrap()
+    );
+    assert_eq!(
+        first_scheduled_day(monday, &[Weekday::Sun]),
+        NaiveDate::from_ymd_opt(2024, 6, 9).unwrap()
+    );
+    assert_eq!(first_scheduled_day(monday, &[Weekday::Mon]), monday);
+    assert_eq!(first_scheduled_day(monday, &[]), monday);
+}
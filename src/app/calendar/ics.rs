@@ -0,0 +1,175 @@
+use std::fmt::Write;
+
+use chrono::{DateTime, NaiveDate, Utc, Weekday};
+
+/// Longest a content line may be, in octets, before it must be folded (RFC 5545 section 3.1)
+const MAX_LINE_OCTETS: usize = 75;
+
+/// How often a calendar event repeats, rendered as an `RRULE`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Recurrence {
+    /// Every `interval` days
+    Daily { interval: u32 },
+    /// Every week on the given days
+    Weekly { days: Vec<Weekday> },
+}
+
+/// A repeating reminder in a calendar feed
+#[derive(Clone, Debug)]
+pub struct CalendarEvent {
+    /// Must stay the same between renders so calendar apps update rather than duplicate the event
+    pub uid: String,
+    pub summary: String,
+    pub description: Option<String>,
+    pub starts_at: DateTime<Utc>,
+    pub recurrence: Recurrence,
+    /// Last day the event repeats on
+    pub until: Option<NaiveDate>,
+    /// How long before the event to show an alarm
+    pub alarm_minutes_before: u32,
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn format_weekday(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+/// Escapes a TEXT value (RFC 5545 section 3.3.11)
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes a content line, folding it onto continuation lines so that none are longer than
+/// `MAX_LINE_OCTETS`, without splitting a UTF-8 character
+fn write_line(out: &mut String, line: &str) {
+    let mut line_octets = 0;
+    for c in line.chars() {
+        if line_octets + c.len_utf8() > MAX_LINE_OCTETS {
+            out.push_str("\r\n ");
+            // The leading space counts towards the continuation line's length
+            line_octets = 1;
+        }
+        out.push(c);
+        line_octets += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn render_rrule(recurrence: &Recurrence, until: Option<NaiveDate>) -> String {
+    let mut rule = match recurrence {
+        Recurrence::Daily { interval } => format!("FREQ=DAILY;INTERVAL={}", interval.max(&1)),
+        Recurrence::Weekly { days } => format!(
+            "FREQ=WEEKLY;BYDAY={}",
+            days.iter()
+                .map(|day| format_weekday(*day))
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+    };
+    if let Some(until) = until.and_then(|until| until.and_hms_opt(23, 59, 59)) {
+        let _ = write!(rule, ";UNTIL={}", format_time(until.and_utc()));
+    }
+    rule
+}
+
+/// Renders events as an iCalendar (RFC 5545) document
+#[must_use]
+pub fn render(name: &str, events: &[CalendarEvent], now: DateTime<Utc>) -> String {
+    let mut out = String::new();
+    write_line(&mut out, "BEGIN:VCALENDAR");
+    write_line(&mut out, "VERSION:2.0");
+    write_line(&mut out, "PRODID:-//BigLNotation//Parkinson's Pulse//EN");
+    write_line(&mut out, "CALSCALE:GREGORIAN");
+    write_line(&mut out, "METHOD:PUBLISH");
+    write_line(&mut out, &format!("X-WR-CALNAME:{}", escape_text(name)));
+
+    for event in events {
+        write_line(&mut out, "BEGIN:VEVENT");
+        write_line(&mut out, &format!("UID:{}", event.uid));
+        write_line(&mut out, &format!("DTSTAMP:{}", format_time(now)));
+        write_line(
+            &mut out,
+            &format!("DTSTART:{}", format_time(event.starts_at)),
+        );
+        write_line(&mut out, "DURATION:PT15M");
+        write_line(
+            &mut out,
+            &format!("RRULE:{}", render_rrule(&event.recurrence, event.until)),
+        );
+        write_line(
+            &mut out,
+            &format!("SUMMARY:{}", escape_text(&event.summary)),
+        );
+        if let Some(description) = &event.description {
+            write_line(
+                &mut out,
+                &format!("DESCRIPTION:{}", escape_text(description)),
+            );
+        }
+        write_line(&mut out, "BEGIN:VALARM");
+        write_line(&mut out, "ACTION:DISPLAY");
+        write_line(
+            &mut out,
+            &format!("DESCRIPTION:{}", escape_text(&event.summary)),
+        );
+        write_line(
+            &mut out,
+            &format!("TRIGGER:-PT{}M", event.alarm_minutes_before),
+        );
+        write_line(&mut out, "END:VALARM");
+        write_line(&mut out, "END:VEVENT");
+    }
+
+    write_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+#[test]
+fn renders_folded_and_escaped_events() {
+    let starts_at = DateTime::parse_from_rfc3339("2024-06-03T08:30:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    let event = CalendarEvent {
+        uid: String::from("abc@parkinsons-pulse"),
+        summary: String::from("Take Sinemet; 25/100mg, with food"),
+        description: Some("é".repeat(60)),
+        starts_at,
+        recurrence: Recurrence::Weekly {
+            days: vec![Weekday::Mon, Weekday::Thu],
+        },
+        until: NaiveDate::from_ymd_opt(2024, 12, 31),
+        alarm_minutes_before: 10,
+    };
+
+    let calendar = render("Medication", &[event], starts_at);
+
+    assert!(calendar.contains("SUMMARY:Take Sinemet\\; 25/100mg\\, with food\r\n"));
+    assert!(calendar.contains("RRULE:FREQ=WEEKLY;BYDAY=MO,TH;UNTIL=20241231T235959Z\r\n"));
+    assert!(calendar.contains("TRIGGER:-PT10M\r\n"));
+    for line in calendar.split("\r\n") {
+        assert!(line.len() <= MAX_LINE_OCTETS, "line too long: {line}");
+    }
+    assert!(calendar.contains("\r\n é"));
+}
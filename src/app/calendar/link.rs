@@ -0,0 +1,88 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use mongodb::{bson::doc, Database};
+use serde_json::json;
+
+use crate::app::{
+    auth::middleware::Auth,
+    models::{generate_random_string, User},
+};
+
+const CALENDAR_TOKEN_LENGTH: usize = 32;
+
+fn feed_path(calendar_token: &str) -> String {
+    format!("/calendar/feed/{calendar_token}.ics")
+}
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn find_link(State(db): State<Database>, Auth(auth): Auth) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to access your calendar feed"),
+        )
+            .into_response();
+    };
+
+    let result = db
+        .collection::<User>("users")
+        .find_one(doc! { "_id": auth.id })
+        .await;
+    match result {
+        Ok(Some(User {
+            calendar_token: Some(calendar_token),
+            ..
+        })) => (
+            StatusCode::OK,
+            Json(json!({ "feed_path": feed_path(&calendar_token) })),
+        )
+            .into_response(),
+        Ok(..) => (
+            StatusCode::NOT_FOUND,
+            String::from("You have not created a calendar feed"),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Creates a new secret for the user's calendar feed, so any old feed URL stops working
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn rotate_link(State(db): State<Database>, Auth(auth): Auth) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to create a calendar feed"),
+        )
+            .into_response();
+    };
+
+    let calendar_token = generate_random_string(CALENDAR_TOKEN_LENGTH);
+    let result = db
+        .collection::<User>("users")
+        .update_one(
+            doc! { "_id": auth.id },
+            doc! { "$set": { "calendar_token": &calendar_token } },
+        )
+        .await;
+    match result {
+        Ok(..) => (
+            StatusCode::OK,
+            Json(json!({ "feed_path": feed_path(&calendar_token) })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -0,0 +1,19 @@
+mod feed;
+pub mod ics;
+mod link;
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+use feed::feed;
+use link::{find_link, rotate_link};
+
+use super::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/link", get(find_link))
+        .route("/rotate", post(rotate_link))
+        .route("/feed/:feed", get(feed))
+}
@@ -0,0 +1,352 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use mongodb::{
+    bson::{doc, oid::ObjectId},
+    Database,
+};
+
+use super::access_log::record_access;
+use crate::app::models::{CaregiverScope, User};
+
+/// Why someone may not do something with a patient's data
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessDenied {
+    /// There is no such patient, or they are in an organisation the actor can't see into
+    PatientNotFound,
+    /// The patient isn't in the organisation the actor belongs to
+    OtherOrganisation,
+    NotCaregiver,
+    MissingScope(CaregiverScope),
+}
+
+impl AccessDenied {
+    #[must_use]
+    pub fn status(self) -> StatusCode {
+        match self {
+            AccessDenied::PatientNotFound => StatusCode::NOT_FOUND,
+            AccessDenied::NotCaregiver => StatusCode::UNAUTHORIZED,
+            AccessDenied::OtherOrganisation | AccessDenied::MissingScope(..) => {
+                StatusCode::FORBIDDEN
+            }
+        }
+    }
+}
+
+impl IntoResponse for AccessDenied {
+    fn into_response(self) -> Response {
+        let message = match self {
+            AccessDenied::PatientNotFound => String::from("Could not find patient"),
+            AccessDenied::OtherOrganisation => {
+                String::from("This patient is not in your organisation")
+            }
+            AccessDenied::NotCaregiver => String::from("You are not a caregiver of this patient"),
+            AccessDenied::MissingScope(scope) => {
+                format!("This patient has not given you the {scope:?} permission")
+            }
+        };
+        (self.status(), message).into_response()
+    }
+}
+
+/// Checks that `actor_id` may do what `scope` allows with the data of `patient`, by either being
+/// that patient or a caregiver they have given the scope to. Caregivers who belong to an
+/// organisation (`actor_organisation_id`) must also share it with the patient.
+///
+/// # Errors
+/// Returns why access is denied
+pub fn authorize(
+    patient: &User,
+    actor_id: ObjectId,
+    actor_organisation_id: Option<ObjectId>,
+    scope: CaregiverScope,
+) -> Result<(), AccessDenied> {
+    if patient.id == Some(actor_id) {
+        return Ok(());
+    }
+    if !patient.is_in_tenant_of(actor_organisation_id) {
+        return Err(AccessDenied::OtherOrganisation);
+    }
+    match patient.caregiver_grant(actor_id) {
+        None => Err(AccessDenied::NotCaregiver),
+        Some(grant) if grant.has_scope(scope) => Ok(()),
+        Some(..) => Err(AccessDenied::MissingScope(scope)),
+    }
+}
+
+/// Finds the organisation a user belongs to
+///
+/// This reads it from the database rather than the user's token, so someone who leaves an
+/// organisation loses access to its patients straight away.
+///
+/// # Errors
+/// Returns an error if the database could not be queried
+pub async fn organisation_of(
+    db: &Database,
+    user_id: ObjectId,
+) -> mongodb::error::Result<Option<ObjectId>> {
+    let user = db
+        .collection::<User>("users")
+        .find_one(doc! { "_id": user_id })
+        .await?;
+    Ok(user.and_then(|user| user.organisation_id))
+}
+
+/// Decides whether `actor_id`, who belongs to `actor_organisation_id`, may do what `scope` allows
+/// with the data of the patient that was looked up. Patients in other organisations are treated as
+/// if they don't exist.
+///
+/// # Errors
+/// Returns why access is denied
+pub fn patient_access(
+    patient: Option<User>,
+    actor_id: ObjectId,
+    actor_organisation_id: Option<ObjectId>,
+    scope: CaregiverScope,
+) -> Result<User, AccessDenied> {
+    let patient = patient
+        .filter(|patient| {
+            patient.id == Some(actor_id) || patient.is_in_tenant_of(actor_organisation_id)
+        })
+        .ok_or(AccessDenied::PatientNotFound)?;
+    authorize(&patient, actor_id, actor_organisation_id, scope)?;
+    Ok(patient)
+}
+
+/// Finds a patient and checks that `actor_id` may do what `scope` allows with their data, logging
+/// the access if they are a caregiver
+///
+/// # Errors
+/// Returns the response that should be sent back if access is denied or the database could not be queried
+pub async fn check_access(
+    db: &Database,
+    actor_id: ObjectId,
+    patient_id: ObjectId,
+    scope: CaregiverScope,
+) -> Result<User, Response> {
+    let actor_organisation_id = match organisation_of(db, actor_id).await {
+        Ok(organisation_id) => organisation_id,
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        }
+    };
+    let patient = match db
+        .collection::<User>("users")
+        .find_one(doc! { "_id": patient_id })
+        .await
+    {
+        Ok(patient) => patient,
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        }
+    };
+    let patient = patient_access(patient, actor_id, actor_organisation_id, scope)
+        .map_err(IntoResponse::into_response)?;
+    if actor_id != patient_id {
+        if let Err(e) = record_access(db, patient_id, actor_id, scope).await {
+            tracing::error!(error = %e, "Failed to record caregiver access");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        }
+    }
+    Ok(patient)
+}
+
+#[test]
+fn caregivers_need_the_right_scope() {
+    use crate::app::models::CaregiverGrant;
+    use mongodb::bson::DateTime;
+
+    let caregiver_id = ObjectId::new();
+    let mut patient = User::from(
+        String::from("Pat"),
+        String::from("Ient"),
+        String::from("patient@example.com"),
+        String::new(),
+        true,
+    );
+    patient.id = Some(ObjectId::new());
+    patient.caregivers.push(CaregiverGrant {
+        caregiver_id,
+        scopes: vec![CaregiverScope::ViewSymptoms],
+        granted_at: DateTime::now(),
+        expires_at: None,
+    });
+    let expired_caregiver_id = ObjectId::new();
+    patient.caregivers.push(CaregiverGrant {
+        caregiver_id: expired_caregiver_id,
+        scopes: CaregiverScope::DEFAULT.to_vec(),
+        granted_at: DateTime::from_millis(0),
+        expires_at: Some(DateTime::from_millis(1)),
+    });
+
+    assert!(authorize(
+        &patient,
+        patient.id.unwrap(),
+        None,
+        CaregiverScope::EditForms
+    )
+    .is_ok());
+    assert!(authorize(&patient, caregiver_id, None, CaregiverScope::ViewSymptoms).is_ok());
+    assert_eq!(
+        authorize(&patient, caregiver_id, None, CaregiverScope::ViewFreeText)
+            .unwrap_err()
+            .status(),
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        authorize(
+            &patient,
+            expired_caregiver_id,
+            None,
+            CaregiverScope::ViewSymptoms
+        )
+        .unwrap_err()
+        .status(),
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        authorize(
+            &patient,
+            ObjectId::new(),
+            None,
+            CaregiverScope::ViewSymptoms
+        )
+        .unwrap_err()
+        .status(),
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[test]
+fn organisations_are_kept_apart() {
+    use crate::app::models::CaregiverGrant;
+    use mongodb::bson::DateTime;
+
+    let clinic = ObjectId::new();
+    let other_clinic = ObjectId::new();
+    let mut patient = User::from(
+        String::from("Pat"),
+        String::from("Ient"),
+        String::from("patient@example.com"),
+        String::new(),
+        true,
+    );
+    patient.id = Some(ObjectId::new());
+    patient.organisation_id = Some(clinic);
+    let clinician_id = ObjectId::new();
+    let family_id = ObjectId::new();
+    for caregiver_id in [clinician_id, family_id] {
+        patient.caregivers.push(CaregiverGrant {
+            caregiver_id,
+            scopes: vec![CaregiverScope::ViewSymptoms],
+            granted_at: DateTime::now(),
+            expires_at: None,
+        });
+    }
+
+    assert!(authorize(
+        &patient,
+        clinician_id,
+        Some(clinic),
+        CaregiverScope::ViewSymptoms
+    )
+    .is_ok());
+    // A grant isn't enough once the clinician belongs to another organisation
+    assert_eq!(
+        authorize(
+            &patient,
+            clinician_id,
+            Some(other_clinic),
+            CaregiverScope::ViewSymptoms
+        )
+        .unwrap_err()
+        .status(),
+        StatusCode::FORBIDDEN
+    );
+    assert!(authorize(&patient, family_id, None, CaregiverScope::ViewSymptoms).is_ok());
+    assert!(authorize(
+        &patient,
+        patient.id.unwrap(),
+        Some(other_clinic),
+        CaregiverScope::EditForms
+    )
+    .is_ok());
+
+    // Patients who haven't been enrolled are out of reach of every organisation
+    patient.organisation_id = None;
+    assert!(!patient.is_in_tenant_of(Some(clinic)));
+    assert!(patient.is_in_tenant_of(None));
+}
+
+#[test]
+fn other_organisations_cannot_reach_patients() {
+    use crate::app::models::CaregiverGrant;
+    use mongodb::bson::DateTime;
+
+    // Medication endpoints check the first two, and form endpoints the rest
+    let scopes = [
+        CaregiverScope::ViewMedications,
+        CaregiverScope::ManageMedications,
+        CaregiverScope::ViewSymptoms,
+        CaregiverScope::ViewFreeText,
+        CaregiverScope::SubmitOnBehalf,
+        CaregiverScope::EditForms,
+    ];
+    let clinic = ObjectId::new();
+    let other_clinic = ObjectId::new();
+    let mut patient = User::from(
+        String::from("Pat"),
+        String::from("Ient"),
+        String::from("patient@example.com"),
+        String::new(),
+        true,
+    );
+    let patient_id = ObjectId::new();
+    patient.id = Some(patient_id);
+    patient.organisation_id = Some(clinic);
+    let caregiver_id = ObjectId::new();
+    patient.caregivers.push(CaregiverGrant {
+        caregiver_id,
+        scopes: scopes.to_vec(),
+        granted_at: DateTime::now(),
+        expires_at: None,
+    });
+
+    for scope in scopes {
+        assert!(patient_access(Some(patient.clone()), caregiver_id, Some(clinic), scope).is_ok());
+        assert!(patient_access(Some(patient.clone()), caregiver_id, None, scope).is_ok());
+        // Even with a grant, the patient is hidden from a caregiver in another organisation
+        assert_eq!(
+            patient_access(
+                Some(patient.clone()),
+                caregiver_id,
+                Some(other_clinic),
+                scope
+            )
+            .unwrap_err(),
+            AccessDenied::PatientNotFound
+        );
+        assert!(
+            patient_access(Some(patient.clone()), patient_id, Some(other_clinic), scope).is_ok()
+        );
+        assert_eq!(
+            patient_access(None, caregiver_id, Some(clinic), scope).unwrap_err(),
+            AccessDenied::PatientNotFound
+        );
+    }
+
+    patient.organisation_id = None;
+    assert_eq!(
+        patient_access(
+            Some(patient),
+            caregiver_id,
+            Some(clinic),
+            CaregiverScope::ViewMedications
+        )
+        .unwrap_err(),
+        AccessDenied::PatientNotFound
+    );
+}
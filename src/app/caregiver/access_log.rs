@@ -0,0 +1,109 @@
+use axum::{
+    extract::{MatchedPath, Query, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, DateTime},
+    Database,
+};
+
+use crate::app::{
+    auth::middleware::Auth,
+    models::{dto::caregiver::FindAccessLogQuery, AccessLogEntry, CaregiverScope},
+};
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 200;
+
+tokio::task_local! {
+    /// Method and route of the request being handled
+    static ENDPOINT: String;
+}
+
+/// Remembers which endpoint is being called so that caregiver accesses can be logged against it
+pub async fn track_endpoint(request: Request, next: Next) -> Response {
+    let endpoint = format!(
+        "{} {}",
+        request.method(),
+        request
+            .extensions()
+            .get::<MatchedPath>()
+            .map_or_else(|| request.uri().path(), MatchedPath::as_str)
+    );
+    ENDPOINT.scope(endpoint, next.run(request)).await
+}
+
+/// Adds an entry to the access log for a caregiver using `scope` on a patient's data
+///
+/// # Errors
+/// Returns an error if the entry could not be stored
+pub async fn record_access(
+    db: &Database,
+    patient_id: ObjectId,
+    caregiver_id: ObjectId,
+    scope: CaregiverScope,
+) -> mongodb::error::Result<()> {
+    let entry = AccessLogEntry {
+        id: ObjectId::new(),
+        patient_id,
+        caregiver_id,
+        scope,
+        action: scope.action(),
+        resource: scope.resource(),
+        endpoint: ENDPOINT.try_with(Clone::clone).ok(),
+        accessed_at: DateTime::now(),
+    };
+    db.collection::<AccessLogEntry>("caregiver_access_log")
+        .insert_one(entry)
+        .await?;
+    Ok(())
+}
+
+/// Lists when the signed-in user's caregivers have read or changed their data, newest first
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn find_access_log(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Query(query): Query<FindAccessLogQuery>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to view who has accessed your data"),
+        )
+            .into_response();
+    };
+
+    let mut filter = doc! { "patient_id": auth.id };
+    if let Some(caregiver_id) = query.caregiver_id {
+        filter.insert("caregiver_id", caregiver_id);
+    }
+    if let Some(before) = query.before {
+        filter.insert("accessed_at", doc! { "$lt": DateTime::from_chrono(before) });
+    }
+
+    let result = db
+        .collection::<AccessLogEntry>("caregiver_access_log")
+        .find(filter)
+        .sort(doc! { "accessed_at": -1 })
+        .limit(query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT))
+        .await;
+    match result {
+        Ok(cursor) => match cursor.try_collect::<Vec<AccessLogEntry>>().await {
+            Ok(entries) => (StatusCode::OK, Json(entries)).into_response(),
+            Err(e) => {
+                tracing::error!(error = %e, "Error occurred while reading the access log");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        },
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
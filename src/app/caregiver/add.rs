@@ -17,11 +17,13 @@ use crate::app::{
     auth::{self, middleware::Auth},
     models::{
         dto::{caregiver::CaregiverTokenPath, form::CreateFormPayload},
-        CaregiverToken, Form, User,
+        CaregiverGrant, CaregiverRequest, CaregiverRequestStatus, CaregiverToken, Form,
+        Notification, NotificationKind, User,
     },
+    notification::notify,
 };
 
-use super::delete_expired_tokens;
+use super::{access::organisation_of, delete_expired_tokens};
 
 #[tracing::instrument]
 #[axum::debug_handler]
@@ -60,17 +62,6 @@ pub async fn add_caregiver(
         return (StatusCode::NOT_FOUND, String::from("Could not find token")).into_response();
     };
 
-    match db
-        .collection::<CaregiverToken>("caregiver_tokens")
-        .delete_one(doc! {
-          "token": path.token.clone()
-        })
-        .await
-    {
-        Ok(..) => {}
-        Err(..) => {}
-    }
-
     let user_id = found_token.user_id;
 
     if user_id == auth.id {
@@ -81,21 +72,156 @@ pub async fn add_caregiver(
             .into_response();
     }
 
-    match db
+    // Members of an organisation can only look after its own patients
+    let patient = db
         .collection::<User>("users")
-        .find_one_and_update(
+        .find_one(doc! { "_id": user_id })
+        .await;
+    match (patient, organisation_of(&db, auth.id).await) {
+        (Ok(Some(patient)), Ok(organisation_id)) if !patient.is_in_tenant_of(organisation_id) => {
+            return (
+                StatusCode::FORBIDDEN,
+                String::from("You can only become a caregiver of patients in your organisation"),
+            )
+                .into_response()
+        }
+        // Leave the token for someone it's of use to
+        (Ok(Some(patient)), Ok(..)) if patient.caregiver_grant(auth.id).is_some() => {
+            return (
+                StatusCode::CONFLICT,
+                String::from("You are already this patient's caregiver"),
+            )
+                .into_response()
+        }
+        (Ok(..), Ok(..)) => {}
+        (Err(e), _) | (_, Err(e)) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    }
+
+    let now = DateTime::now();
+    if found_token
+        .access_expires_at
+        .is_some_and(|expires_at| expires_at <= now)
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            String::from("The access this token gives has already expired"),
+        )
+            .into_response();
+    }
+
+    // Tokens can only be used once, so claim it before anyone else can
+    match db
+        .collection::<CaregiverToken>("caregiver_tokens")
+        .update_one(
             doc! {
-              "_id": user_id
+              "_id": found_token.id,
+              "redeemed_by": null
             },
             doc! {
-              "$addToSet": {
-                "caregivers": auth.id
+              "$set": {
+                "redeemed_by": auth.id,
+                "redeemed_at": now
               }
             },
         )
         .await
     {
-        Ok(..) => StatusCode::OK.into_response(),
+        Ok(result) if result.modified_count == 0 => {
+            return (
+                StatusCode::BAD_REQUEST,
+                String::from("This token has already been used"),
+            )
+                .into_response()
+        }
+        Ok(..) => {}
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    }
+
+    let grant = CaregiverGrant {
+        caregiver_id: auth.id,
+        scopes: found_token.scopes,
+        granted_at: now,
+        expires_at: found_token.access_expires_at,
+    };
+
+    // Untrusted tokens could have been passed on to anyone, so the patient has to approve them
+    if !found_token.trusted {
+        let request = CaregiverRequest {
+            id: ObjectId::new(),
+            patient_id: user_id,
+            token_id: found_token.id,
+            caregiver_id: auth.id,
+            caregiver_first_name: auth.first_name.clone(),
+            caregiver_last_name: auth.last_name.clone(),
+            caregiver_email_address: auth.email_address.clone(),
+            scopes: grant.scopes,
+            access_expires_at: grant.expires_at,
+            status: CaregiverRequestStatus::Pending,
+            requested_at: now,
+            decided_at: None,
+        };
+        let request_id = request.id;
+        if let Err(e) = db
+            .collection::<CaregiverRequest>("caregiver_requests")
+            .insert_one(request)
+            .await
+        {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+        notify(
+            &db,
+            Notification::from(
+                user_id,
+                NotificationKind::CaregiverRequested {
+                    request_id,
+                    caregiver_id: auth.id,
+                },
+                String::from("Caregiver request"),
+                format!(
+                    "{} {} ({}) has asked to become your caregiver",
+                    auth.first_name, auth.last_name, auth.email_address
+                ),
+            ),
+        )
+        .await;
+        return (
+            StatusCode::ACCEPTED,
+            Json(json!({ "request_id": request_id })),
+        )
+            .into_response();
+    }
+
+    match add_grant(&db, user_id, &grant).await {
+        Ok(false) => (
+            StatusCode::CONFLICT,
+            String::from("You are already this patient's caregiver"),
+        )
+            .into_response(),
+        Ok(true) => {
+            notify(
+                &db,
+                Notification::from(
+                    user_id,
+                    NotificationKind::CaregiverAdded {
+                        caregiver_id: auth.id,
+                    },
+                    String::from("New caregiver"),
+                    format!(
+                        "{} {} is now your caregiver",
+                        auth.first_name, auth.last_name
+                    ),
+                ),
+            )
+            .await;
+            StatusCode::OK.into_response()
+        }
         Err(err) => {
             tracing::error!("{:#?}", err);
             (
@@ -106,3 +232,40 @@ pub async fn add_caregiver(
         .into_response(),
     }
 }
+
+/// Makes someone a patient's caregiver, unless they already are one
+///
+/// Returns whether they were added
+pub(crate) async fn add_grant(
+    db: &Database,
+    patient_id: ObjectId,
+    grant: &CaregiverGrant,
+) -> mongodb::error::Result<bool> {
+    let grant_document = to_document(grant)?;
+    let users = db.collection::<User>("users");
+    // An expired grant may not have been swept up yet, and shouldn't stop a new one being added
+    users
+        .update_one(
+            doc! { "_id": patient_id },
+            doc! { "$pull": { "caregivers": {
+              "caregiver_id": grant.caregiver_id,
+              "expires_at": { "$lte": DateTime::now() }
+            } } },
+        )
+        .await?;
+    let patient = users
+        .find_one_and_update(
+            doc! {
+              "_id": patient_id,
+              // Existing caregivers keep the scopes they already have
+              "caregivers.caregiver_id": { "$ne": grant.caregiver_id }
+            },
+            doc! {
+              "$push": {
+                "caregivers": grant_document
+              }
+            },
+        )
+        .await?;
+    Ok(patient.is_some())
+}
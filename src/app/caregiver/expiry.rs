@@ -0,0 +1,82 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use mongodb::{
+    bson::{doc, DateTime},
+    Database,
+};
+
+use crate::app::{
+    auth::middleware::Auth,
+    models::{
+        dto::caregiver::{CaregiverExpiryPath, CaregiverExpiryPayload},
+        User,
+    },
+};
+
+/// Changes when one of the signed-in user's caregivers loses access, or lets them keep it until
+/// they are removed
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn set_expiry(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<CaregiverExpiryPath>,
+    Json(payload): Json<CaregiverExpiryPayload>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to change caregiver access"),
+        )
+            .into_response();
+    };
+
+    if payload
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= Utc::now())
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            String::from("Caregiver access must expire in the future"),
+        )
+            .into_response();
+    }
+
+    match db
+        .collection::<User>("users")
+        .update_one(
+            doc! {
+              "_id": auth.id,
+              "caregivers.caregiver_id": path.caregiver_id
+            },
+            doc! { "$set": {
+                "caregivers.$.expires_at": payload.expires_at.map(DateTime::from_chrono)
+            } },
+        )
+        .await
+    {
+        Ok(result) => {
+            if result.matched_count == 0 {
+                return (
+                    StatusCode::NOT_FOUND,
+                    String::from("Could not find caregiver"),
+                )
+                    .into_response();
+            }
+            StatusCode::OK.into_response()
+        }
+        Err(err) => {
+            tracing::error!("{:#?}", err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                String::from("Could not change caregiver access"),
+            )
+                .into_response()
+        }
+    }
+}
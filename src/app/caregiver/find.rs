@@ -52,14 +52,55 @@ pub async fn find_caregiver(State(db): State<Database>, Auth(auth): Auth) -> Res
         doc! {
             "$lookup": {
                 "from": "users",  // The caregivers collection
-                "localField": "caregivers",  // The field in users that holds caregiver IDs
+                "localField": "caregivers.caregiver_id",  // The field in users that holds caregiver IDs
                 "foreignField": "_id",  // The field in caregivers that matches the user_id
                 "as": "caregivers_info"  // The result will be stored in this field
             }
         },
+        // Attach what each caregiver has been allowed to do
+        doc! {
+            "$addFields": {
+                "caregivers_info": {
+                    "$map": {
+                        "input": "$caregivers_info",
+                        "as": "info",
+                        "in": {
+                            "$let": {
+                                "vars": {
+                                    "grant": {
+                                        "$arrayElemAt": [
+                                            {
+                                                "$filter": {
+                                                    "input": "$caregivers",
+                                                    "cond": { "$eq": ["$$this.caregiver_id", "$$info._id"] }
+                                                }
+                                            },
+                                            0
+                                        ]
+                                    }
+                                },
+                                "in": {
+                                    "$mergeObjects": [
+                                        "$$info",
+                                        {
+                                            "scopes": "$$grant.scopes",
+                                            "granted_at": "$$grant.granted_at",
+                                            "expires_at": "$$grant.expires_at"
+                                        }
+                                    ]
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        },
         doc! {
             "$project": {
                 "caregivers_info._id": 1,
+                "caregivers_info.scopes": 1,
+                "caregivers_info.granted_at": 1,
+                "caregivers_info.expires_at": 1,
                 "caregivers_info.first_name": 1,  // Only retrieve caregiver ID and name
                 "caregivers_info.last_name": 1,  // Only retrieve caregiver ID and name
                 "caregivers_info.email_address": 1,  // Only retrieve caregiver ID and name
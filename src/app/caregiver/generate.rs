@@ -6,7 +6,7 @@ use axum::{
 };
 use chrono::{Duration, Utc};
 use mongodb::{
-    bson::{doc, oid::ObjectId, to_document, Bson},
+    bson::{doc, oid::ObjectId, to_document, Bson, DateTime},
     Database,
 };
 use serde::{Deserialize, Serialize};
@@ -14,11 +14,20 @@ use serde_json::json;
 
 use crate::app::{
     auth::{self, middleware::Auth},
-    models::{dto::form::CreateFormPayload, CaregiverToken, Form, User},
+    models::{
+        dto::{caregiver::GenerateTokenPayload, form::CreateFormPayload},
+        CaregiverScope, CaregiverToken, Form, User,
+    },
 };
 
 use super::delete_expired_tokens;
 
+/// How long a token can be used for if the patient doesn't choose
+const DEFAULT_VALID_FOR_HOURS: i64 = 72;
+
+const MAX_VALID_FOR_HOURS: i64 = 24 * 14;
+
+/// Creates a token that gives whoever uses it the default caregiver scopes
 #[tracing::instrument]
 #[axum::debug_handler]
 pub async fn generate(State(db): State<Database>, Auth(auth): Auth) -> Response {
@@ -30,7 +39,76 @@ pub async fn generate(State(db): State<Database>, Auth(auth): Auth) -> Response
             .into_response();
     };
 
-    let caregiver_token = CaregiverToken::new(auth.id.clone());
+    create_token(
+        &db,
+        auth.id,
+        CaregiverScope::DEFAULT.to_vec(),
+        None,
+        Duration::hours(DEFAULT_VALID_FOR_HOURS),
+        false,
+    )
+    .await
+}
+
+/// Creates a token that gives whoever uses it the scopes chosen by the patient
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn generate_with_scopes(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Json(payload): Json<GenerateTokenPayload>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to generate a caregiver token"),
+        )
+            .into_response();
+    };
+
+    if payload
+        .access_expires_at
+        .is_some_and(|expires_at| expires_at <= Utc::now())
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            String::from("Caregiver access must expire in the future"),
+        )
+            .into_response();
+    }
+
+    let valid_for_hours = payload
+        .valid_for_hours
+        .map_or(DEFAULT_VALID_FOR_HOURS, i64::from);
+    if !(1..=MAX_VALID_FOR_HOURS).contains(&valid_for_hours) {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("Tokens must be valid for between 1 and {MAX_VALID_FOR_HOURS} hours"),
+        )
+            .into_response();
+    }
+
+    create_token(
+        &db,
+        auth.id,
+        payload.scopes,
+        payload.access_expires_at.map(DateTime::from_chrono),
+        Duration::hours(valid_for_hours),
+        payload.trusted,
+    )
+    .await
+}
+
+async fn create_token(
+    db: &Database,
+    user_id: ObjectId,
+    scopes: Vec<CaregiverScope>,
+    access_expires_at: Option<DateTime>,
+    valid_for: Duration,
+    trusted: bool,
+) -> Response {
+    let mut caregiver_token = CaregiverToken::new(user_id, scopes, access_expires_at, valid_for);
+    caregiver_token.trusted = trusted;
 
     let result = db
         .collection::<CaregiverToken>("caregiver_tokens")
@@ -39,7 +117,11 @@ pub async fn generate(State(db): State<Database>, Auth(auth): Auth) -> Response
     match result {
         Ok(..) => (
             StatusCode::OK,
-            Json(json! ({ "created_token": caregiver_token.token })),
+            Json(json! ({
+                "created_token": caregiver_token.token,
+                "invitation_id": caregiver_token.id,
+                "expired_by": caregiver_token.expired_by,
+            })),
         )
             .into_response(),
         Err(e) => {
@@ -0,0 +1,109 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, DateTime},
+    Database,
+};
+
+use crate::app::{
+    auth::middleware::Auth,
+    models::{
+        dto::caregiver::{FindInvitationsQuery, InvitationPath},
+        CaregiverToken,
+    },
+};
+
+/// Lists the caregiver tokens the signed-in user has generated that can still be used
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn find_invitations(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Query(query): Query<FindInvitationsQuery>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to view caregiver invitations"),
+        )
+            .into_response();
+    };
+
+    let filter = if query.include_redeemed {
+        doc! {
+          "user_id": auth.id,
+          "$or": [
+            { "redeemed_by": { "$ne": null } },
+            { "expired_by": { "$gt": DateTime::now() } }
+          ]
+        }
+    } else {
+        doc! {
+          "user_id": auth.id,
+          "redeemed_by": null,
+          "expired_by": { "$gt": DateTime::now() }
+        }
+    };
+
+    let result = db
+        .collection::<CaregiverToken>("caregiver_tokens")
+        .find(filter)
+        .sort(doc! { "created_at": -1 })
+        .await;
+    match result {
+        Ok(cursor) => match cursor.try_collect::<Vec<CaregiverToken>>().await {
+            Ok(invitations) => (StatusCode::OK, Json(invitations)).into_response(),
+            Err(e) => {
+                tracing::error!(error = %e, "Error occurred while reading caregiver tokens");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        },
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Cancels a caregiver token so that it can no longer be used
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn revoke_invitation(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<InvitationPath>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to revoke a caregiver invitation"),
+        )
+            .into_response();
+    };
+
+    let result = db
+        .collection::<CaregiverToken>("caregiver_tokens")
+        .delete_one(doc! {
+          "_id": path.invitation_id,
+          "user_id": auth.id,
+          "redeemed_by": null
+        })
+        .await;
+    match result {
+        Ok(result) if result.deleted_count == 0 => (
+            StatusCode::NOT_FOUND,
+            String::from("Could not find an unused invitation to revoke"),
+        )
+            .into_response(),
+        Ok(..) => StatusCode::OK.into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -1,10 +1,18 @@
+pub mod access;
+pub mod access_log;
 pub mod add;
+pub mod expiry;
 pub mod find;
 pub mod generate;
+pub mod invitations;
+pub mod patients;
+pub mod qr;
 pub mod remove;
+pub mod requests;
+pub mod scopes;
 
 use axum::{
-    routing::{delete, get, post},
+    routing::{delete, get, patch, post},
     Router,
 };
 use bson::{doc, DateTime};
@@ -19,10 +27,12 @@ async fn delete_expired_tokens(
 ) -> mongodb::error::Result<()> {
     let now = DateTime::now();
 
+    // Used tokens are kept as a record of who used them
     let filter = doc! {
       "expired_by": {
         "$lt": now
-      }
+      },
+      "redeemed_by": null
     };
 
     let delete_result = collection.delete_many(filter).await?;
@@ -37,7 +47,30 @@ async fn delete_expired_tokens(
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/list", get(find::find_caregiver))
-        .route("/generate", get(generate::generate))
+        .route("/access-log", get(access_log::find_access_log))
+        .route(
+            "/generate",
+            get(generate::generate).post(generate::generate_with_scopes),
+        )
+        .route("/invitations", get(invitations::find_invitations))
+        .route(
+            "/invitations/:invitation_id",
+            delete(invitations::revoke_invitation),
+        )
+        .route("/invitations/:invitation_id/qr", get(qr::invitation_qr))
         .route("/add/:token", post(add::add_caregiver))
+        .route("/requests", get(requests::find_requests))
+        .route(
+            "/requests/:request_id/approve",
+            post(requests::approve_request),
+        )
+        .route(
+            "/requests/:request_id/reject",
+            post(requests::reject_request),
+        )
         .route("/remove/:caregiver_id", delete(remove::remove_caregiver))
+        .route("/patients", get(patients::find_patients))
+        .route("/patients/:patient_id", delete(patients::leave_patient))
+        .route("/scopes/:caregiver_id", patch(scopes::set_scopes))
+        .route("/expiry/:caregiver_id", patch(expiry::set_expiry))
 }
@@ -0,0 +1,195 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, from_document, oid::ObjectId, DateTime, Document},
+    Database,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{access::organisation_of, access_log::record_access};
+use crate::app::{
+    auth::middleware::Auth,
+    form::find::{get_users_symptoms, Symptom},
+    models::{
+        dto::caregiver::CaredForPatientPath, CaregiverGrant, CaregiverScope, Notification,
+        NotificationKind, User,
+    },
+    notification::notify,
+};
+
+#[derive(Serialize, Deserialize)]
+struct PatientInfo {
+    #[serde(rename = "_id")]
+    id: ObjectId,
+    first_name: String,
+    last_name: String,
+    email_address: String,
+    /// What the patient has allowed the caregiver to do
+    grant: CaregiverGrant,
+    /// Left out if the caregiver isn't allowed to view the patient's symptoms
+    #[serde(default)]
+    symptoms: Option<Vec<Symptom>>,
+}
+
+/// Filter for the patients a caregiver in `organisation_id` currently looks after
+fn cared_for_filter(
+    caregiver_id: ObjectId,
+    organisation_id: Option<ObjectId>,
+    now: DateTime,
+) -> Document {
+    let mut filter = doc! {
+      "caregivers": {
+        "$elemMatch": {
+          "caregiver_id": caregiver_id,
+          "$and": [CaregiverGrant::active_filter(now)]
+        }
+      }
+    };
+    filter.extend(User::tenant_filter(organisation_id));
+    filter
+}
+
+/// Lists the patients the signed-in user is a caregiver of
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn find_patients(State(db): State<Database>, Auth(auth): Auth) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to find patients"),
+        )
+            .into_response();
+    };
+
+    let organisation_id = match organisation_of(&db, auth.id).await {
+        Ok(organisation_id) => organisation_id,
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let now = DateTime::now();
+    let pipeline = vec![
+        doc! { "$match": cared_for_filter(auth.id, organisation_id, now) },
+        // Only keep what the patient gave to this caregiver
+        doc! {
+          "$project": {
+            "first_name": 1,
+            "last_name": 1,
+            "email_address": 1,
+            "grant": {
+              "$arrayElemAt": [
+                {
+                  "$filter": {
+                    "input": "$caregivers",
+                    "cond": { "$eq": ["$$this.caregiver_id", auth.id] }
+                  }
+                },
+                0
+              ]
+            }
+          }
+        },
+        doc! { "$sort": { "last_name": 1, "first_name": 1 } },
+    ];
+
+    let result = db.collection::<User>("users").aggregate(pipeline).await;
+    let documents = match result {
+        Ok(cursor) => match cursor.try_collect::<Vec<_>>().await {
+            Ok(documents) => documents,
+            Err(e) => {
+                tracing::error!(error = %e, "Error occurred while reading patients");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        },
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let mut patients = Vec::with_capacity(documents.len());
+    for document in documents {
+        let mut patient: PatientInfo = match from_document(document) {
+            Ok(patient) => patient,
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to read patient");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        };
+        if patient.grant.has_scope(CaregiverScope::ViewSymptoms) {
+            if let Err(e) =
+                record_access(&db, patient.id, auth.id, CaregiverScope::ViewSymptoms).await
+            {
+                tracing::error!(error = %e, "Failed to record caregiver access");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+            match get_users_symptoms(patient.id, &db).await {
+                Ok(symptoms) => patient.symptoms = Some(symptoms),
+                Err(..) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+            }
+        }
+        patients.push(patient);
+    }
+
+    (StatusCode::OK, Json(patients)).into_response()
+}
+
+/// Stops the signed-in user being a caregiver of a patient
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn leave_patient(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<CaredForPatientPath>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to stop being a caregiver"),
+        )
+            .into_response();
+    };
+
+    let result = db
+        .collection::<User>("users")
+        .update_one(
+            doc! { "_id": path.patient_id },
+            doc! { "$pull": { "caregivers": { "caregiver_id": auth.id } } },
+        )
+        .await;
+    match result {
+        Ok(result) if result.modified_count == 0 => (
+            StatusCode::NOT_FOUND,
+            String::from("You are not a caregiver of this patient"),
+        )
+            .into_response(),
+        Ok(..) => {
+            notify(
+                &db,
+                Notification::from(
+                    path.patient_id,
+                    NotificationKind::CaregiverLeft {
+                        caregiver_id: auth.id,
+                    },
+                    String::from("Caregiver left"),
+                    format!(
+                        "{} {} is no longer your caregiver",
+                        auth.first_name, auth.last_name
+                    ),
+                ),
+            )
+            .await;
+            StatusCode::OK.into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::io::Cursor;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use image::{ImageFormat, Luma};
+use mongodb::{
+    bson::{doc, DateTime},
+    Database,
+};
+use qrcode::{render::svg, QrCode};
+
+use crate::{
+    app::{
+        auth::middleware::Auth,
+        models::{
+            dto::caregiver::{InvitationPath, QrFormat, QrQuery},
+            CaregiverToken,
+        },
+    },
+    config,
+};
+
+/// Size of each QR code module in pixels. Large enough to scan from a phone held at arm's length.
+const MODULE_PIXELS: u32 = 8;
+
+/// Renders one of the signed-in user's caregiver invitations as a QR code, which opens a link
+/// to accept it
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn invitation_qr(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<InvitationPath>,
+    Query(query): Query<QrQuery>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to view a caregiver invitation"),
+        )
+            .into_response();
+    };
+
+    let invitation = match db
+        .collection::<CaregiverToken>("caregiver_tokens")
+        .find_one(doc! {
+          "_id": path.invitation_id,
+          "user_id": auth.id,
+          "redeemed_by": null,
+          "expired_by": { "$gt": DateTime::now() }
+        })
+        .await
+    {
+        Ok(Some(invitation)) => invitation,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                String::from("Could not find an unused invitation"),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let link = format!(
+        "{}/{}",
+        config::get_invite_link_base().trim_end_matches('/'),
+        invitation.token
+    );
+    let code = match QrCode::new(link) {
+        Ok(code) => code,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to create QR code");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    match query.format {
+        QrFormat::Png => match render_png(&code) {
+            Ok(png) => ([(header::CONTENT_TYPE, "image/png")], png).into_response(),
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to render QR code");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        },
+        QrFormat::Svg => {
+            let image = code.render::<svg::Color>().min_dimensions(256, 256).build();
+            ([(header::CONTENT_TYPE, "image/svg+xml")], image).into_response()
+        }
+    }
+}
+
+/// Renders a QR code as a black and white PNG, with the blank border scanners need to find it
+fn render_png(code: &QrCode) -> image::ImageResult<Vec<u8>> {
+    let image = code
+        .render::<Luma<u8>>()
+        .module_dimensions(MODULE_PIXELS, MODULE_PIXELS)
+        .build();
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+#[test]
+fn renders_a_png_that_decodes() {
+    let code = QrCode::new("parkinsonspulse://caregiver/add/abcdefghij").unwrap();
+
+    let png = render_png(&code).unwrap();
+
+    let image = image::load_from_memory_with_format(&png, ImageFormat::Png)
+        .unwrap()
+        .to_luma8();
+    let quiet_zone = 4 * MODULE_PIXELS;
+    let size = u32::try_from(code.width()).unwrap() * MODULE_PIXELS + 2 * quiet_zone;
+    assert_eq!(image.dimensions(), (size, size));
+    assert_eq!(image.get_pixel(0, 0).0, [255]);
+    // The top left module is the corner of a finder pattern, which is always dark
+    assert_eq!(image.get_pixel(quiet_zone, quiet_zone).0, [0]);
+}
@@ -20,8 +20,9 @@ use crate::app::{
             caregiver::{CaregiverTokenPath, RemoveCaregiverPath},
             form::CreateFormPayload,
         },
-        CaregiverToken, Form, User,
+        CaregiverToken, Form, Notification, NotificationKind, User,
     },
+    notification::notify,
 };
 
 #[tracing::instrument]
@@ -49,13 +50,32 @@ pub async fn remove_caregiver(
             },
             doc! {
               "$pull": {
-                "caregivers": path.caregiver_id
+                "caregivers": { "caregiver_id": path.caregiver_id }
               }
             },
         )
         .await
     {
-        Ok(..) => StatusCode::OK.into_response(),
+        Ok(patient) => {
+            if patient.is_some_and(|patient| patient.caregiver_grant(path.caregiver_id).is_some()) {
+                notify(
+                    &db,
+                    Notification::from(
+                        path.caregiver_id,
+                        NotificationKind::CaregiverRemoved {
+                            patient_id: auth.id,
+                        },
+                        String::from("Removed as caregiver"),
+                        format!(
+                            "{} {} has removed you as their caregiver",
+                            auth.first_name, auth.last_name
+                        ),
+                    ),
+                )
+                .await;
+            }
+            StatusCode::OK.into_response()
+        }
         Err(err) => {
             tracing::error!("{:#?}", err);
             (
@@ -0,0 +1,241 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson, DateTime},
+    options::ReturnDocument,
+    Database,
+};
+
+use crate::app::{
+    auth::middleware::Auth,
+    models::{
+        dto::caregiver::CaregiverRequestPath, CaregiverGrant, CaregiverRequest,
+        CaregiverRequestStatus, Notification, NotificationKind,
+    },
+    notification::notify,
+};
+
+use super::add::add_grant;
+
+/// Lists the requests to become the signed-in user's caregiver that are waiting for approval
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn find_requests(State(db): State<Database>, Auth(auth): Auth) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to view caregiver requests"),
+        )
+            .into_response();
+    };
+
+    let result = db
+        .collection::<CaregiverRequest>("caregiver_requests")
+        .find(doc! { "patient_id": auth.id, "status": "Pending" })
+        .sort(doc! { "requested_at": -1 })
+        .await;
+    match result {
+        Ok(cursor) => match cursor.try_collect::<Vec<CaregiverRequest>>().await {
+            Ok(requests) => (StatusCode::OK, Json(requests)).into_response(),
+            Err(e) => {
+                tracing::error!(error = %e, "Error occurred while reading caregiver requests");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        },
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Marks a pending request as approved or rejected, returning it if it was still pending
+async fn decide(
+    db: &Database,
+    patient_id: ObjectId,
+    request_id: ObjectId,
+    status: CaregiverRequestStatus,
+) -> Result<CaregiverRequest, Response> {
+    let Ok(status) = to_bson(&status) else {
+        return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+    };
+    match db
+        .collection::<CaregiverRequest>("caregiver_requests")
+        .find_one_and_update(
+            doc! { "_id": request_id, "patient_id": patient_id, "status": "Pending" },
+            doc! { "$set": { "status": status, "decided_at": DateTime::now() } },
+        )
+        .return_document(ReturnDocument::After)
+        .await
+    {
+        Ok(Some(request)) => Ok(request),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            String::from("Could not find a pending caregiver request"),
+        )
+            .into_response()),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+}
+
+/// Makes the person who sent a request one of the signed-in user's caregivers
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn approve_request(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<CaregiverRequestPath>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to approve a caregiver"),
+        )
+            .into_response();
+    };
+
+    // Time-limited access may have run out while the request was waiting
+    let now = DateTime::now();
+    let expired = match db
+        .collection::<CaregiverRequest>("caregiver_requests")
+        .find_one(doc! { "_id": path.request_id, "patient_id": auth.id, "status": "Pending" })
+        .await
+    {
+        Ok(Some(request)) => request
+            .access_expires_at
+            .is_some_and(|expires_at| expires_at <= now),
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                String::from("Could not find a pending caregiver request"),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    if expired {
+        if let Err(response) = decide(
+            &db,
+            auth.id,
+            path.request_id,
+            CaregiverRequestStatus::Rejected,
+        )
+        .await
+        {
+            return response;
+        }
+        return (
+            StatusCode::BAD_REQUEST,
+            String::from("The access this caregiver asked for has already expired"),
+        )
+            .into_response();
+    }
+
+    let request = match decide(
+        &db,
+        auth.id,
+        path.request_id,
+        CaregiverRequestStatus::Approved,
+    )
+    .await
+    {
+        Ok(request) => request,
+        Err(response) => return response,
+    };
+
+    let grant = CaregiverGrant {
+        caregiver_id: request.caregiver_id,
+        scopes: request.scopes,
+        granted_at: now,
+        expires_at: request.access_expires_at,
+    };
+    match add_grant(&db, auth.id, &grant).await {
+        Ok(true) => {}
+        // Their existing access is left as it is, rather than silently replaced
+        Ok(false) => {
+            return (
+                StatusCode::CONFLICT,
+                String::from("This person is already your caregiver"),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    }
+
+    notify(
+        &db,
+        Notification::from(
+            request.caregiver_id,
+            NotificationKind::CaregiverRequestApproved {
+                patient_id: auth.id,
+            },
+            String::from("Caregiver request approved"),
+            format!(
+                "You are now {} {}'s caregiver",
+                auth.first_name, auth.last_name
+            ),
+        ),
+    )
+    .await;
+    StatusCode::OK.into_response()
+}
+
+/// Turns down a request to become the signed-in user's caregiver
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn reject_request(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<CaregiverRequestPath>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to reject a caregiver"),
+        )
+            .into_response();
+    };
+
+    let request = match decide(
+        &db,
+        auth.id,
+        path.request_id,
+        CaregiverRequestStatus::Rejected,
+    )
+    .await
+    {
+        Ok(request) => request,
+        Err(response) => return response,
+    };
+
+    notify(
+        &db,
+        Notification::from(
+            request.caregiver_id,
+            NotificationKind::CaregiverRequestRejected {
+                patient_id: auth.id,
+            },
+            String::from("Caregiver request rejected"),
+            format!(
+                "{} {} did not approve your request to become their caregiver",
+                auth.first_name, auth.last_name
+            ),
+        ),
+    )
+    .await;
+    StatusCode::OK.into_response()
+}
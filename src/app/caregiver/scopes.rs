@@ -0,0 +1,71 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use mongodb::{
+    bson::{doc, to_bson},
+    Database,
+};
+
+use crate::app::{
+    auth::middleware::Auth,
+    models::{
+        dto::caregiver::{CaregiverScopesPath, CaregiverScopesPayload},
+        User,
+    },
+};
+
+/// Changes what one of the signed-in user's caregivers is allowed to do
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn set_scopes(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<CaregiverScopesPath>,
+    Json(payload): Json<CaregiverScopesPayload>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to change caregiver access"),
+        )
+            .into_response();
+    };
+
+    let Ok(scopes) = to_bson(&payload.scopes) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+
+    match db
+        .collection::<User>("users")
+        .update_one(
+            doc! {
+              "_id": auth.id,
+              "caregivers.caregiver_id": path.caregiver_id
+            },
+            doc! { "$set": { "caregivers.$.scopes": scopes } },
+        )
+        .await
+    {
+        Ok(result) => {
+            if result.matched_count == 0 {
+                return (
+                    StatusCode::NOT_FOUND,
+                    String::from("Could not find caregiver"),
+                )
+                    .into_response();
+            }
+            StatusCode::OK.into_response()
+        }
+        Err(err) => {
+            tracing::error!("{:#?}", err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                String::from("Could not change caregiver access"),
+            )
+                .into_response()
+        }
+    }
+}
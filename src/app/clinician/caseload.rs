@@ -0,0 +1,270 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Duration, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, DateTime, Document},
+    Database,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{
+    auth::{middleware::Auth, role::require_role},
+    caregiver::access_log::record_access,
+    medication::{adherence::get_adherence, stock::get_stock_statuses},
+    models::{
+        dto::clinician::CaseloadQuery, CaregiverGrant, CaregiverScope, Form, Role, User,
+        OVERDUE_AFTER_HOURS,
+    },
+};
+
+const DEFAULT_PER_PAGE: u64 = 20;
+const MAX_PER_PAGE: u64 = 100;
+
+/// How many days of doses the adherence figure covers
+const ADHERENCE_DAYS: i64 = 7;
+
+/// Missing this many doses of a medication in a row raises an alert
+const MISSED_DOSES_ALERT_STREAK: usize = 3;
+
+#[derive(Serialize, Deserialize)]
+struct OverdueForm {
+    form_id: Option<ObjectId>,
+    title: String,
+    due_at: DateTime,
+}
+
+#[derive(Serialize, Deserialize)]
+enum CaseloadAlert {
+    LowStock {
+        medication_id: ObjectId,
+        medication_name: String,
+        days_remaining: Option<f64>,
+    },
+    MissedDoses {
+        medication_id: ObjectId,
+        medication_name: String,
+        missed_in_a_row: usize,
+    },
+}
+
+/// A patient in a clinician's caseload. Sections the patient hasn't allowed the clinician to see
+/// are left as `null`.
+#[derive(Serialize, Deserialize)]
+struct CaseloadEntry {
+    patient_id: ObjectId,
+    first_name: String,
+    last_name: String,
+    scopes: Vec<CaregiverScope>,
+    last_submission_at: Option<DateTime>,
+    overdue_forms: Option<Vec<OverdueForm>>,
+    alerts: Option<Vec<CaseloadAlert>>,
+    /// Percentage of the doses due in the last `ADHERENCE_DAYS` days that were taken
+    adherence_percent: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Caseload {
+    patients: Vec<CaseloadEntry>,
+    page: u64,
+    per_page: u64,
+    total: u64,
+}
+
+/// Filter for the patients in a clinician's caseload. Only patients in the clinician's organisation
+/// are included, even if others have given them access.
+fn caseload_filter(
+    clinician_id: ObjectId,
+    organisation_id: Option<ObjectId>,
+    now: DateTime,
+) -> Document {
+    let mut filter = doc! {
+      "caregivers": {
+        "$elemMatch": {
+          "caregiver_id": clinician_id,
+          "$and": [CaregiverGrant::active_filter(now)]
+        }
+      }
+    };
+    filter.extend(User::tenant_filter(organisation_id));
+    filter
+}
+
+/// Lists the patients a clinician has been linked to, with what needs their attention
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn caseload(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Query(query): Query<CaseloadQuery>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to view your caseload"),
+        )
+            .into_response();
+    };
+
+    let clinician = match require_role(&db, auth.id, &[Role::Clinician]).await {
+        Ok(clinician) => clinician,
+        Err(response) => return response,
+    };
+
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query
+        .per_page
+        .unwrap_or(DEFAULT_PER_PAGE)
+        .clamp(1, MAX_PER_PAGE);
+    let filter = caseload_filter(auth.id, clinician.organisation_id, DateTime::now());
+
+    let users = db.collection::<User>("users");
+    let total = match users.count_documents(filter.clone()).await {
+        Ok(total) => total,
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let patients = match users
+        .find(filter)
+        .sort(doc! { "last_name": 1, "first_name": 1, "_id": 1 })
+        .skip((page - 1) * per_page)
+        .limit(i64::try_from(per_page).unwrap_or(i64::MAX))
+        .await
+    {
+        Ok(cursor) => cursor.try_collect::<Vec<User>>().await,
+        Err(e) => Err(e),
+    };
+    let patients = match patients {
+        Ok(patients) => patients,
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let mut entries = Vec::with_capacity(patients.len());
+    for patient in patients {
+        match caseload_entry(&db, auth.id, &patient).await {
+            Ok(Some(entry)) => entries.push(entry),
+            Ok(None) => {}
+            Err(e) => {
+                tracing::error!(error = %e, "Error occurred while building caseload");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        }
+    }
+
+    (
+        StatusCode::OK,
+        Json(Caseload {
+            patients: entries,
+            page,
+            per_page,
+            total,
+        }),
+    )
+        .into_response()
+}
+
+async fn caseload_entry(
+    db: &Database,
+    clinician_id: ObjectId,
+    patient: &User,
+) -> mongodb::error::Result<Option<CaseloadEntry>> {
+    let (Some(patient_id), Some(grant)) = (patient.id, patient.caregiver_grant(clinician_id))
+    else {
+        return Ok(None);
+    };
+    let now = Utc::now();
+    let mut entry = CaseloadEntry {
+        patient_id,
+        first_name: patient.first_name.clone(),
+        last_name: patient.last_name.clone(),
+        scopes: grant.scopes.clone(),
+        last_submission_at: None,
+        overdue_forms: None,
+        alerts: None,
+        adherence_percent: None,
+    };
+
+    if grant.has_scope(CaregiverScope::ViewSymptoms) {
+        record_access(db, patient_id, clinician_id, CaregiverScope::ViewSymptoms).await?;
+        let forms = db
+            .collection::<Form>("forms")
+            .find(doc! { "user_id": patient_id })
+            .await?
+            .try_collect::<Vec<Form>>()
+            .await?;
+        entry.last_submission_at = forms.iter().filter_map(Form::last_submitted_at).max();
+        entry.overdue_forms = Some(
+            forms
+                .iter()
+                .filter_map(|form| {
+                    let due_at = form.overdue_since(now, Duration::hours(OVERDUE_AFTER_HOURS))?;
+                    Some(OverdueForm {
+                        form_id: form.id,
+                        title: form.title.clone(),
+                        due_at: DateTime::from_chrono(due_at),
+                    })
+                })
+                .collect(),
+        );
+    }
+
+    if grant.has_scope(CaregiverScope::ViewMedications) {
+        record_access(
+            db,
+            patient_id,
+            clinician_id,
+            CaregiverScope::ViewMedications,
+        )
+        .await?;
+        let mut alerts = get_stock_statuses(db, patient_id)
+            .await?
+            .into_iter()
+            .filter(|status| status.low_stock)
+            .map(|status| CaseloadAlert::LowStock {
+                medication_id: status.medication_id,
+                medication_name: status.medication_name,
+                days_remaining: status.days_remaining,
+            })
+            .collect::<Vec<_>>();
+
+        let adherence =
+            get_adherence(db, patient_id, now - Duration::days(ADHERENCE_DAYS), now).await?;
+        let expected = adherence
+            .iter()
+            .map(|medication| medication.stats.expected_doses)
+            .sum::<usize>();
+        let logged = adherence
+            .iter()
+            .map(|medication| medication.stats.logged_doses)
+            .sum::<usize>();
+        #[allow(clippy::cast_precision_loss)]
+        {
+            entry.adherence_percent =
+                (expected > 0).then(|| logged as f64 / expected as f64 * 100.0);
+        }
+        alerts.extend(
+            adherence
+                .into_iter()
+                .filter(|medication| {
+                    medication.stats.current_missed_streak >= MISSED_DOSES_ALERT_STREAK
+                })
+                .map(|medication| CaseloadAlert::MissedDoses {
+                    medication_id: medication.medication_id,
+                    medication_name: medication.medication_name,
+                    missed_in_a_row: medication.stats.current_missed_streak,
+                }),
+        );
+        entry.alerts = Some(alerts);
+    }
+
+    Ok(Some(entry))
+}
@@ -0,0 +1,10 @@
+mod caseload;
+
+use axum::{routing::get, Router};
+use caseload::caseload;
+
+use super::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/caseload", get(caseload))
+}
@@ -0,0 +1,51 @@
+mod outbox;
+mod smtp;
+
+use std::{fmt::Debug, sync::Arc};
+
+use anyhow::bail;
+use axum::async_trait;
+
+pub use outbox::{ConsoleEmailSender, FileEmailSender};
+pub use smtp::SmtpEmailSender;
+
+use crate::config;
+
+/// A plain text email to one person
+#[derive(Clone, Debug)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Something that can deliver emails
+#[async_trait]
+pub trait EmailSender: Send + Sync + Debug {
+    /// # Errors
+    /// Returns an error if the email could not be handed over for delivery
+    async fn send(&self, email: Email) -> anyhow::Result<()>;
+}
+
+/// Picks how emails are sent. SMTP is used if `SMTP_URL` is set, otherwise emails are written to
+/// `EMAIL_OUTBOX_DIR`, or logged if that isn't set either. Production must use SMTP, as the other
+/// two would leave password reset links where anyone with access to the server could use them.
+///
+/// # Errors
+/// Returns an error if `SMTP_URL` or `EMAIL_FROM` are invalid, or `SMTP_URL` isn't set in
+/// production
+pub fn from_config() -> anyhow::Result<Arc<dyn EmailSender>> {
+    if let Some(url) = config::get_smtp_url() {
+        return Ok(Arc::new(SmtpEmailSender::new(
+            &url,
+            &config::get_email_from(),
+        )?));
+    }
+    if config::get_is_production() == "PRODUCTION" {
+        bail!("SMTP_URL must be set in production");
+    }
+    match config::get_email_outbox_dir() {
+        Some(directory) => Ok(Arc::new(FileEmailSender::new(directory.into()))),
+        None => Ok(Arc::new(ConsoleEmailSender)),
+    }
+}
@@ -0,0 +1,78 @@
+use std::path::PathBuf;
+
+use axum::async_trait;
+use chrono::Utc;
+
+use super::{Email, EmailSender};
+use crate::app::models::generate_random_string;
+
+/// Writes each email to a file instead of sending it, for trying things out locally
+#[derive(Debug)]
+pub struct FileEmailSender {
+    directory: PathBuf,
+}
+
+impl FileEmailSender {
+    #[must_use]
+    pub fn new(directory: PathBuf) -> FileEmailSender {
+        FileEmailSender { directory }
+    }
+}
+
+#[async_trait]
+impl EmailSender for FileEmailSender {
+    async fn send(&self, email: Email) -> anyhow::Result<()> {
+        tokio::fs::create_dir_all(&self.directory).await?;
+        let path = self.directory.join(format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%dT%H%M%S%.3f"),
+            generate_random_string(6)
+        ));
+        let contents = format!(
+            "To: {}\r\nSubject: {}\r\n\r\n{}\r\n",
+            email.to, email.subject, email.body
+        );
+        tokio::fs::write(&path, contents).await?;
+        tracing::info!("Wrote email to {}", path.display());
+        Ok(())
+    }
+}
+
+/// Logs each email instead of sending it
+#[derive(Debug)]
+pub struct ConsoleEmailSender;
+
+#[async_trait]
+impl EmailSender for ConsoleEmailSender {
+    async fn send(&self, email: Email) -> anyhow::Result<()> {
+        tracing::info!(
+            to = email.to,
+            subject = email.subject,
+            "Email not sent, as no way of sending it is set up:\n{}",
+            email.body
+        );
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn file_sender_writes_each_email() {
+    let directory = std::env::temp_dir().join(format!("pulse-outbox-{}", std::process::id()));
+    let sender = FileEmailSender::new(directory.clone());
+
+    sender
+        .send(Email {
+            to: String::from("patient@example.com"),
+            subject: String::from("Reset your password"),
+            body: String::from("Open this link"),
+        })
+        .await
+        .unwrap();
+
+    let mut files = std::fs::read_dir(&directory).unwrap();
+    let contents = std::fs::read_to_string(files.next().unwrap().unwrap().path()).unwrap();
+    assert!(files.next().is_none());
+    assert!(contents.starts_with("To: patient@example.com\r\nSubject: Reset your password\r\n"));
+    assert!(contents.contains("Open this link"));
+    std::fs::remove_dir_all(directory).unwrap();
+}
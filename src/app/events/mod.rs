@@ -0,0 +1,148 @@
+//! Live updates for connected clients, over a WebSocket (`/events/ws`) or Server-Sent Events
+//! (`/events/stream`)
+//!
+//! This only works with a single instance of the service. Events go through an in-process bus, so
+//! clients connected to one instance never hear about events published by another. Clients that
+//! reconnect catch up by asking for stored events with a later ID, which only works while the
+//! IDs are created by one process.
+
+mod stream;
+mod ws;
+
+use std::sync::OnceLock;
+
+use axum::{routing::get, Router};
+use mongodb::{
+    bson::{doc, oid::ObjectId, DateTime},
+    Database,
+};
+use serde::{Deserialize, Serialize};
+use stream::stream;
+use tokio::sync::broadcast;
+use ws::connect;
+
+use super::{
+    models::{CaregiverScope, Notification, User, WebhookEventType},
+    webhook::enqueue_deliveries,
+    AppState,
+};
+
+/// How many events can be waiting for the slowest client before it starts missing them
+const BUS_CAPACITY: usize = 1024;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/ws", get(connect))
+        .route("/stream", get(stream))
+}
+
+/// Something that happened which connected clients should know about straight away
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum LiveEvent {
+    FormSubmitted {
+        patient_id: ObjectId,
+        form_id: ObjectId,
+        submitted_by: ObjectId,
+    },
+    /// A medication was added, updated, stopped or restarted
+    MedicationChanged {
+        patient_id: ObjectId,
+        medication_id: ObjectId,
+    },
+    Notification(Notification),
+}
+
+impl LiveEvent {
+    #[must_use]
+    pub fn webhook_event_type(&self) -> WebhookEventType {
+        match self {
+            LiveEvent::FormSubmitted { .. } => WebhookEventType::FormSubmitted,
+            LiveEvent::MedicationChanged { .. } => WebhookEventType::MedicationChanged,
+            LiveEvent::Notification(..) => WebhookEventType::Notification,
+        }
+    }
+
+    /// What a caregiver must have been allowed to do to be sent this event
+    #[must_use]
+    pub fn required_scope(&self) -> Option<CaregiverScope> {
+        match self {
+            LiveEvent::FormSubmitted { .. } => Some(CaregiverScope::ViewSymptoms),
+            LiveEvent::MedicationChanged { .. } => Some(CaregiverScope::ViewMedications),
+            LiveEvent::Notification(..) => None,
+        }
+    }
+}
+
+/// A live event along with the users allowed to see it. These are also stored for a while so
+/// that clients which reconnect can catch up on what they missed.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Envelope {
+    /// Increases with each event published by this instance, so clients can ask for everything
+    /// after the last one they saw
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub audience: Vec<ObjectId>,
+    pub event: LiveEvent,
+    pub created_at: DateTime,
+}
+
+/// In-process bus that every connected client listens on
+fn bus() -> &'static broadcast::Sender<Envelope> {
+    static BUS: OnceLock<broadcast::Sender<Envelope>> = OnceLock::new();
+    BUS.get_or_init(|| broadcast::channel(BUS_CAPACITY).0)
+}
+
+#[must_use]
+pub fn subscribe() -> broadcast::Receiver<Envelope> {
+    bus().subscribe()
+}
+
+/// Stores an event and sends it to every connected client in `audience`
+pub async fn publish(db: &Database, audience: Vec<ObjectId>, event: LiveEvent) {
+    let envelope = Envelope {
+        id: ObjectId::new(),
+        audience,
+        event,
+        created_at: DateTime::now(),
+    };
+    let result = db
+        .collection::<Envelope>("events")
+        .insert_one(&envelope)
+        .await;
+    // Clients that are connected can still be sent the event, even if it can't be replayed later
+    if let Err(e) = result {
+        tracing::error!(error = %e, "Failed to store event");
+    }
+    enqueue_deliveries(db, &envelope).await;
+    // This only fails if nobody is connected, which is fine
+    let _ = bus().send(envelope);
+}
+
+/// Sends an event to a patient and each of their caregivers allowed to see it
+pub async fn publish_to_patient_circle(db: &Database, patient_id: ObjectId, event: LiveEvent) {
+    let mut audience = vec![patient_id];
+    match db
+        .collection::<User>("users")
+        .find_one(doc! { "_id": patient_id })
+        .await
+    {
+        Ok(Some(patient)) => {
+            let now = DateTime::now();
+            audience.extend(
+                patient
+                    .caregivers
+                    .iter()
+                    .filter(|grant| grant.is_active(now))
+                    .filter(|grant| {
+                        event
+                            .required_scope()
+                            .is_none_or(|scope| grant.has_scope(scope))
+                    })
+                    .map(|grant| grant.caregiver_id),
+            );
+        }
+        Ok(None) => {}
+        Err(e) => tracing::error!(error = %e, "Failed to find caregivers to send event to"),
+    }
+    publish(db, audience, event).await;
+}
@@ -0,0 +1,130 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use futures::{stream, Stream, StreamExt, TryStreamExt};
+use mongodb::{
+    bson::{doc, oid::ObjectId},
+    Database,
+};
+use serde_json::json;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+use crate::app::{
+    auth::{middleware::Auth, session::session_ended},
+    events::{subscribe, Envelope},
+};
+
+/// Most events sent to a client catching up after reconnecting
+const MAX_REPLAYED: i64 = 500;
+
+fn to_sse_event(envelope: &Envelope) -> Event {
+    match Event::default()
+        .id(envelope.id.to_hex())
+        .json_data(&envelope.event)
+    {
+        Ok(event) => event,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to serialize live event");
+            Event::default().id(envelope.id.to_hex()).comment("skipped")
+        }
+    }
+}
+
+/// Tells the client it missed events so it can fetch what it needs again
+fn lagged_event(missed: u64) -> Event {
+    Event::default().data(json!({ "Lagged": { "missed": missed } }).to_string())
+}
+
+/// Streams live events for `user_id` that are newer than `last_seen`
+fn live_events(
+    events: Receiver<Envelope>,
+    user_id: ObjectId,
+    last_seen: Option<ObjectId>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    stream::unfold(events, move |mut events| async move {
+        loop {
+            match events.recv().await {
+                Ok(envelope)
+                    if envelope.audience.contains(&user_id)
+                        // Skip events that were already replayed from the database
+                        && last_seen.is_none_or(|last_seen| envelope.id > last_seen) =>
+                {
+                    return Some((Ok(to_sse_event(&envelope)), events));
+                }
+                Ok(..) => {}
+                Err(RecvError::Lagged(missed)) => return Some((Ok(lagged_event(missed)), events)),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
+}
+
+/// Streams the signed-in user's live events as Server-Sent Events. Clients that reconnect with a
+/// `Last-Event-ID` header are first sent any stored events they missed. The stream ends once the
+/// session it was opened with ends.
+#[tracing::instrument(skip(headers))]
+#[axum::debug_handler]
+pub async fn stream(State(db): State<Database>, Auth(auth): Auth, headers: HeaderMap) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to receive live updates"),
+        )
+            .into_response();
+    };
+
+    // Subscribe before reading stored events so nothing published in between is lost
+    let events = subscribe();
+
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| ObjectId::parse_str(value).ok());
+    let mut replayed = Vec::new();
+    if let Some(last_event_id) = last_event_id {
+        let result = db
+            .collection::<Envelope>("events")
+            .find(doc! { "audience": auth.id, "_id": { "$gt": last_event_id } })
+            .sort(doc! { "_id": 1 })
+            .limit(MAX_REPLAYED)
+            .await;
+        match result {
+            Ok(cursor) => match cursor.try_collect::<Vec<Envelope>>().await {
+                Ok(envelopes) => replayed = envelopes,
+                Err(e) => {
+                    tracing::error!(error = %e, "Error occurred while reading events");
+                    return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                }
+            },
+            Err(e) => {
+                tracing::error!(error = %e, "Error occurred while querying database");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        }
+    }
+
+    let last_seen = replayed
+        .last()
+        .map(|envelope| envelope.id)
+        .or(last_event_id);
+    let mut catch_up = replayed.iter().map(to_sse_event).collect::<Vec<_>>();
+    if i64::try_from(replayed.len()).is_ok_and(|replayed| replayed >= MAX_REPLAYED) {
+        // There may be more, so the client should fetch what it needs again
+        catch_up.push(lagged_event(0));
+    }
+
+    Sse::new(
+        stream::iter(catch_up.into_iter().map(Ok))
+            .chain(live_events(events, auth.id, last_seen))
+            .take_until(session_ended(db, auth.session_id)),
+    )
+    .keep_alive(KeepAlive::default())
+    .into_response()
+}
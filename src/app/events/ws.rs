@@ -0,0 +1,100 @@
+use std::future::Future;
+
+use axum::{
+    extract::{
+        ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    http::{header::ORIGIN, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use mongodb::{bson::oid::ObjectId, Database};
+use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    app::{
+        auth::{middleware::Auth, session::session_ended},
+        events::subscribe,
+    },
+    config,
+};
+
+/// Opens a WebSocket that sends the signed-in user every live event meant for them, as JSON
+///
+/// CORS doesn't apply to WebSockets, so any site could otherwise open one with the user's cookies.
+/// Only the client's own origin is allowed to connect. The socket is closed once the session it
+/// was opened with ends.
+#[tracing::instrument(skip(headers, upgrade))]
+pub async fn connect(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    if headers.get(ORIGIN) != Some(&config::get_origin_domain()) {
+        return (
+            StatusCode::FORBIDDEN,
+            String::from("Live updates can only be opened from the app"),
+        )
+            .into_response();
+    }
+
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to receive live updates"),
+        )
+            .into_response();
+    };
+
+    let ended = session_ended(db, auth.session_id);
+    upgrade.on_upgrade(move |socket| send_events(socket, auth.id, ended))
+}
+
+async fn send_events(mut socket: WebSocket, user_id: ObjectId, ended: impl Future<Output = ()>) {
+    let mut events = subscribe();
+    tokio::pin!(ended);
+    tracing::debug!(%user_id, "Client connected for live updates");
+    loop {
+        tokio::select! {
+            () = &mut ended => {
+                let close = CloseFrame {
+                    code: close_code::POLICY,
+                    reason: "Session ended".into(),
+                };
+                // The client may already be gone, in which case there is no one to tell
+                let _ = socket.send(Message::Close(Some(close))).await;
+                break;
+            }
+            received = events.recv() => {
+                let message = match received {
+                    Ok(envelope) if envelope.audience.contains(&user_id) => {
+                        match serde_json::to_string(&envelope.event) {
+                            Ok(message) => message,
+                            Err(e) => {
+                                tracing::error!(error = %e, "Failed to serialize live event");
+                                continue;
+                            }
+                        }
+                    }
+                    Ok(..) => continue,
+                    // Tell the client it missed events so it can fetch what it needs again
+                    Err(RecvError::Lagged(missed)) => {
+                        json!({ "Lagged": { "missed": missed } }).to_string()
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if socket.send(Message::Text(message)).await.is_err() {
+                    break;
+                }
+            }
+            received = socket.recv() => match received {
+                // Pings are answered automatically and clients have nothing else to send
+                Some(Ok(Message::Close(..)) | Err(..)) | None => break,
+                Some(Ok(..)) => {}
+            },
+        }
+    }
+    tracing::debug!(%user_id, "Client disconnected from live updates");
+}
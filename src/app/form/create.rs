@@ -38,6 +38,7 @@ pub async fn create_form(
         auth.id,
         auth.id,
         payload.questions,
+        payload.schedule,
     );
     let result = db.collection::<Form>("forms").insert_one(form).await;
     match result {
@@ -0,0 +1,84 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use mongodb::{
+    bson::{doc, to_bson, DateTime},
+    Database,
+};
+
+use crate::app::{
+    auth::middleware::Auth,
+    form::schedule::find_editable_form,
+    models::{
+        dto::form::{EditQuestionPath, EditQuestionPayload},
+        Event, Form, QuestionEdited,
+    },
+};
+
+/// Replaces one of a form's questions, keeping the old version in the form's events
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn edit_question(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<EditQuestionPath>,
+    Json(payload): Json<EditQuestionPayload>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to edit a form"),
+        )
+            .into_response();
+    };
+
+    let mut form = match find_editable_form(&db, auth.id, path.form_id).await {
+        Ok(form) => form,
+        Err(response) => return response,
+    };
+    let Some(question) = form
+        .questions
+        .iter_mut()
+        .find(|question| question.id() == Some(path.question_id))
+    else {
+        return (
+            StatusCode::NOT_FOUND,
+            String::from("Could not find question"),
+        )
+            .into_response();
+    };
+
+    let mut new_question = payload.question;
+    new_question.assign_ids(path.question_id);
+    let event = Event::QuestionEdited(QuestionEdited {
+        question_id: path.question_id,
+        former_question: std::mem::replace(question, new_question),
+        new_question: question.clone(),
+        edited_by: auth.id,
+        edited_at: DateTime::now(),
+    });
+    let (Ok(questions), Ok(event)) = (to_bson(&form.questions), to_bson(&event)) else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+
+    let result = db
+        .collection::<Form>("forms")
+        .update_one(
+            doc! { "_id": path.form_id },
+            doc! {
+                "$set": { "questions": questions },
+                "$push": { "events": event }
+            },
+        )
+        .await;
+    match result {
+        Ok(..) => StatusCode::OK.into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
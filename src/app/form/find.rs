@@ -10,7 +10,7 @@ use chrono::{Duration, Utc};
 use chrono_humanize::HumanTime;
 use futures::StreamExt;
 use mongodb::{
-    bson::{doc, oid::ObjectId, to_document},
+    bson::{doc, oid::ObjectId, to_document, DateTime, Document},
     Cursor, Database,
 };
 use serde::{Deserialize, Serialize};
@@ -18,9 +18,13 @@ use serde_json::json;
 
 use crate::app::{
     auth::{self, middleware::Auth},
+    caregiver::{
+        access::{authorize, organisation_of, patient_access},
+        access_log::record_access,
+    },
     models::{
         dto::form::{CreateFormPayload, FindPath},
-        Event, Form, FormSubmitted, User,
+        CaregiverGrant, CaregiverScope, Event, Form, FormSubmitted, User,
     },
 };
 
@@ -47,8 +51,8 @@ pub async fn find(
         .await;
 
     match result {
-        Ok(data) => {
-            match &data {
+        Ok(mut data) => {
+            match &mut data {
                 Some(form) => {
                     let Ok(Some(user)) = db
                         .collection::<User>("users")
@@ -59,8 +63,38 @@ pub async fn find(
                     else {
                         return (StatusCode::UNAUTHORIZED).into_response();
                     };
-                    if !(user.caregivers.contains(&auth.id) || user.id == Some(auth.id)) {
-                        return (StatusCode::UNAUTHORIZED).into_response();
+                    let Ok(organisation_id) = organisation_of(&db, auth.id).await else {
+                        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                    };
+                    let user = match patient_access(
+                        Some(user),
+                        auth.id,
+                        organisation_id,
+                        CaregiverScope::ViewSymptoms,
+                    ) {
+                        Ok(user) => user,
+                        Err(denied) => return denied.into_response(),
+                    };
+                    let scope = if authorize(
+                        &user,
+                        auth.id,
+                        organisation_id,
+                        CaregiverScope::ViewFreeText,
+                    )
+                    .is_ok()
+                    {
+                        CaregiverScope::ViewFreeText
+                    } else {
+                        form.redact_free_text();
+                        CaregiverScope::ViewSymptoms
+                    };
+                    if let Some(patient_id) = user.id.filter(|id| *id != auth.id) {
+                        if record_access(&db, patient_id, auth.id, scope)
+                            .await
+                            .is_err()
+                        {
+                            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                        }
                     }
                 }
                 None => {}
@@ -100,6 +134,25 @@ async fn get_users_forms(
     }
 }
 
+/// Filter for the patients whose symptoms a caregiver in `organisation_id` can currently see
+fn symptom_viewable_filter(
+    caregiver_id: ObjectId,
+    organisation_id: Option<ObjectId>,
+    now: DateTime,
+) -> Document {
+    let mut filter = doc! {
+      "caregivers": {
+        "$elemMatch": {
+          "caregiver_id": caregiver_id,
+          "scopes": "ViewSymptoms",
+          "$and": [CaregiverGrant::active_filter(now)]
+        }
+      }
+    };
+    filter.extend(User::tenant_filter(organisation_id));
+    filter
+}
+
 #[tracing::instrument]
 #[axum::debug_handler]
 pub async fn find_all(State(db): State<Database>, Auth(auth): Auth) -> Response {
@@ -111,13 +164,17 @@ pub async fn find_all(State(db): State<Database>, Auth(auth): Auth) -> Response
             .into_response();
     };
 
-    let Ok(mut patients): Result<Cursor<User>, mongodb::error::Error> = db
-        .clone()
-        .collection("users")
-        .find(doc! {
-          "caregivers": auth.id
-        })
-        .await
+    let Ok(organisation_id) = organisation_of(&db, auth.id).await else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            String::from("could not query database"),
+        )
+            .into_response();
+    };
+    let filter = symptom_viewable_filter(auth.id, organisation_id, DateTime::now());
+
+    let Ok(mut patients): Result<Cursor<User>, mongodb::error::Error> =
+        db.clone().collection("users").find(filter).await
     else {
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -146,13 +203,32 @@ pub async fn find_all(State(db): State<Database>, Auth(auth): Auth) -> Response
             )
                 .into_response();
         };
-        let Ok(patient_forms) = get_users_forms(patient_id, &db).await else {
+        let Ok(mut patient_forms) = get_users_forms(patient_id, &db).await else {
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 String::from("could not find patients forms"),
             )
                 .into_response();
         };
+        let scope = if authorize(
+            patient,
+            auth.id,
+            organisation_id,
+            CaregiverScope::ViewFreeText,
+        )
+        .is_ok()
+        {
+            CaregiverScope::ViewFreeText
+        } else {
+            patient_forms.iter_mut().for_each(Form::redact_free_text);
+            CaregiverScope::ViewSymptoms
+        };
+        if record_access(&db, patient_id, auth.id, scope)
+            .await
+            .is_err()
+        {
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
         forms.extend(patient_forms);
     }
 
@@ -160,7 +236,7 @@ pub async fn find_all(State(db): State<Database>, Auth(auth): Auth) -> Response
 }
 
 #[derive(Serialize, Deserialize)]
-struct Symptom {
+pub(crate) struct Symptom {
     title: String,
     description: Option<String>,
     status: String,
@@ -168,7 +244,7 @@ struct Symptom {
     recently_completed: bool,
 }
 
-async fn get_users_symptoms(
+pub(crate) async fn get_users_symptoms(
     user_id: ObjectId,
     db: &Database,
 ) -> Result<Vec<Symptom>, mongodb::error::Error> {
@@ -237,13 +313,17 @@ pub async fn symptom_list(State(db): State<Database>, Auth(auth): Auth) -> Respo
             .into_response();
     };
 
-    let Ok(mut patients): Result<Cursor<User>, mongodb::error::Error> = db
-        .clone()
-        .collection("users")
-        .find(doc! {
-          "caregivers": auth.id
-        })
-        .await
+    let Ok(organisation_id) = organisation_of(&db, auth.id).await else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            String::from("could not query database"),
+        )
+            .into_response();
+    };
+    let filter = symptom_viewable_filter(auth.id, organisation_id, DateTime::now());
+
+    let Ok(mut patients): Result<Cursor<User>, mongodb::error::Error> =
+        db.clone().collection("users").find(filter).await
     else {
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -279,6 +359,12 @@ pub async fn symptom_list(State(db): State<Database>, Auth(auth): Auth) -> Respo
             )
                 .into_response();
         };
+        if record_access(&db, patient_id, auth.id, CaregiverScope::ViewSymptoms)
+            .await
+            .is_err()
+        {
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
         symptoms.extend(patient_symptoms);
     }
 
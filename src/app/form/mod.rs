@@ -1,6 +1,8 @@
 mod create;
-mod find;
+mod edit;
+pub(crate) mod find;
 mod history;
+mod schedule;
 mod submit;
 
 use axum::{
@@ -8,8 +10,10 @@ use axum::{
     Router,
 };
 use create::create_form;
+use edit::edit_question;
 use find::{find, find_all, symptom_list};
 use history::history;
+use schedule::set_schedule;
 use submit::submit;
 
 use super::AppState;
@@ -22,4 +26,6 @@ pub fn router() -> Router<AppState> {
         .route("/symptoms", get(symptom_list))
         .route("/submit/:form_id", post(submit))
         .route("/history", get(history))
+        .route("/schedule/:form_id", post(set_schedule))
+        .route("/edit/:form_id/:question_id", post(edit_question))
 }
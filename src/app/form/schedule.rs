@@ -0,0 +1,110 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson},
+    Database,
+};
+
+use crate::app::{
+    auth::middleware::Auth,
+    caregiver::access::check_access,
+    models::{
+        dto::form::{SchedulePath, SchedulePayload},
+        CaregiverScope, Form,
+    },
+};
+
+/// Finds a form and checks that `actor_id` may edit it
+///
+/// # Errors
+/// Returns the response that should be sent back if the form can't be found or edited
+pub(super) async fn find_editable_form(
+    db: &Database,
+    actor_id: ObjectId,
+    form_id: ObjectId,
+) -> Result<Form, Response> {
+    let form = match db
+        .collection::<Form>("forms")
+        .find_one(doc! { "_id": form_id })
+        .await
+    {
+        Ok(Some(form)) => form,
+        Ok(None) => {
+            return Err((StatusCode::NOT_FOUND, String::from("Could not find form")).into_response())
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        }
+    };
+    if let Some(patient_id) = form.user_id {
+        check_access(db, actor_id, patient_id, CaregiverScope::EditForms).await?;
+    } else if form.created_by != actor_id {
+        return Err(StatusCode::FORBIDDEN.into_response());
+    }
+    Ok(form)
+}
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn set_schedule(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<SchedulePath>,
+    Json(payload): Json<SchedulePayload>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to schedule a form"),
+        )
+            .into_response();
+    };
+
+    if payload
+        .schedule
+        .as_ref()
+        .is_some_and(|schedule| schedule.every_days == 0)
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            String::from("A form must be due at least one day apart"),
+        )
+            .into_response();
+    }
+    let Ok(schedule) = to_bson(&payload.schedule) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            String::from("Could not serialize schedule"),
+        )
+            .into_response();
+    };
+
+    if let Err(response) = find_editable_form(&db, auth.id, path.form_id).await {
+        return response;
+    }
+
+    let result = db
+        .collection::<Form>("forms")
+        .update_one(
+            doc! { "_id": path.form_id },
+            doc! { "$set": { "schedule": schedule } },
+        )
+        .await;
+    match result {
+        Ok(result) => {
+            if result.matched_count == 0 {
+                return StatusCode::BAD_REQUEST.into_response();
+            }
+            StatusCode::OK.into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -11,9 +11,11 @@ use mongodb::{
 
 use crate::app::{
     auth::middleware::Auth,
+    caregiver::access::check_access,
+    events::{publish_to_patient_circle, LiveEvent},
     models::{
         dto::form::{SubmitPath, SubmitPayload},
-        Form,
+        CaregiverScope, Form,
     },
 };
 
@@ -34,6 +36,28 @@ pub async fn submit(
             .into_response();
     };
 
+    let form = match db
+        .collection::<Form>("forms")
+        .find_one(doc! { "_id": path.form_id })
+        .await
+    {
+        Ok(Some(form)) => form,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, String::from("Could not find form")).into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    if let Some(patient_id) = form.user_id {
+        if let Err(response) =
+            check_access(&db, auth.id, patient_id, CaregiverScope::SubmitOnBehalf).await
+        {
+            return response;
+        }
+    }
+
     let answers_document = match payload
         .answers
         .iter()
@@ -51,7 +75,7 @@ pub async fn submit(
 
     let result = db
         .collection::<Form>("forms")
-        .update_one(
+        .find_one_and_update(
             doc! { "_id": path.form_id },
             doc! { "$push": { "events": { "FormSubmitted": {
                 "answers": answers_document,
@@ -61,10 +85,22 @@ pub async fn submit(
         )
         .await;
     match result {
-        Ok(result) => {
-            if result.modified_count == 0 {
-                tracing::error!("No update to the database {:#?}", result);
+        Ok(form) => {
+            let Some(form) = form else {
+                tracing::error!("No update to the database, could not find form");
                 return StatusCode::BAD_REQUEST.into_response();
+            };
+            if let Some(patient_id) = form.user_id {
+                publish_to_patient_circle(
+                    &db,
+                    patient_id,
+                    LiveEvent::FormSubmitted {
+                        patient_id,
+                        form_id: path.form_id,
+                        submitted_by: auth.id,
+                    },
+                )
+                .await;
             }
             StatusCode::OK.into_response()
         }
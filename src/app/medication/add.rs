@@ -1,5 +1,5 @@
 use axum::{
-    extract::State,
+    extract::{Path, State},
     http::StatusCode,
     response::{IntoResponse, Response},
     Json,
@@ -12,7 +12,13 @@ use serde_json::json;
 
 use crate::app::{
     auth::middleware::Auth,
-    models::{dto::medication::AddMedicationPayload, MedicationTrackerEntry},
+    caregiver::access::check_access,
+    events::{publish_to_patient_circle, LiveEvent},
+    medication::interactions::warnings_for_medication,
+    models::{
+        dto::medication::{AddMedicationPayload, PatientPath},
+        CaregiverScope, MedicationTrackerEntry,
+    },
 };
 
 // TODO!: input validation wrt. string length, etc
@@ -31,20 +37,77 @@ pub async fn add_medication(
             .into_response();
     };
 
+    add_users_medication(&db, auth.id, auth.id, payload).await
+}
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn add_patient_medication(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<PatientPath>,
+    Json(payload): Json<AddMedicationPayload>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to add a medication"),
+        )
+            .into_response();
+    };
+
+    if let Err(response) = check_access(
+        &db,
+        auth.id,
+        path.patient_id,
+        CaregiverScope::ManageMedications,
+    )
+    .await
+    {
+        return response;
+    }
+
+    add_users_medication(&db, path.patient_id, auth.id, payload).await
+}
+
+async fn add_users_medication(
+    db: &Database,
+    user_id: ObjectId,
+    added_by: ObjectId,
+    payload: AddMedicationPayload,
+) -> Response {
     let id = ObjectId::new();
     let medication = MedicationTrackerEntry::from(
         id,
-        auth.id,
+        user_id,
+        added_by,
         payload.medication_name,
         payload.dose,
         payload.timing,
+        payload.schedule,
     );
     let result = db
         .collection::<MedicationTrackerEntry>("medications")
         .insert_one(medication)
         .await;
     match result {
-        Ok(..) => (StatusCode::OK, Json(json! ({ "created_id": id }))).into_response(),
+        Ok(..) => {
+            publish_to_patient_circle(
+                db,
+                user_id,
+                LiveEvent::MedicationChanged {
+                    patient_id: user_id,
+                    medication_id: id,
+                },
+            )
+            .await;
+            let warnings = warnings_for_medication(db, user_id, id).await;
+            (
+                StatusCode::OK,
+                Json(json! ({ "created_id": id, "warnings": warnings })),
+            )
+                .into_response()
+        }
         Err(e) => {
             tracing::error!(error = %e, "Error occurred while querying database");
             StatusCode::INTERNAL_SERVER_ERROR.into_response()
@@ -0,0 +1,251 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Days, Duration, NaiveTime, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, DateTime},
+    Database,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{
+    auth::middleware::Auth,
+    caregiver::access::check_access,
+    medication::find::get_users_medications,
+    models::{dto::medication::AdherenceQuery, CaregiverScope, DoseLog, DoseStatus},
+};
+
+/// How far a logged dose may be from when it was due and still count towards that dose
+const MATCH_WINDOW: Duration = Duration::hours(4);
+
+/// The longest report that can be asked for, since every expected dose in it is worked out
+const MAX_RANGE_DAYS: i64 = 366;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct AdherenceStats {
+    pub expected_doses: usize,
+    /// Doses that were logged as taken (on time or late) against an expected dose
+    pub logged_doses: usize,
+    pub skipped_doses: usize,
+    pub missed_doses: usize,
+    /// `None` when no doses were expected
+    pub adherence_percent: Option<f64>,
+    /// How late the logged doses were taken, in minutes
+    pub median_lateness_minutes: Option<i64>,
+    pub longest_missed_streak: usize,
+    /// Number of most recent expected doses in a row that were missed
+    pub current_missed_streak: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct MedicationAdherence {
+    pub medication_id: ObjectId,
+    pub medication_name: String,
+    #[serde(flatten)]
+    pub stats: AdherenceStats,
+}
+
+/// Matches logged doses against the doses that were expected and summarises how well the
+/// schedule was kept
+///
+/// `expected` must be sorted. Each log counts towards at most one expected dose, the closest one
+/// within `MATCH_WINDOW`.
+#[must_use]
+pub fn compute_adherence(
+    expected: &[chrono::DateTime<Utc>],
+    logs: &[(chrono::DateTime<Utc>, DoseStatus)],
+) -> AdherenceStats {
+    let mut matched: Vec<Option<DoseStatus>> = vec![None; expected.len()];
+    let mut lateness = Vec::new();
+
+    let mut logs = logs.to_vec();
+    logs.sort_by_key(|(taken_at, _)| *taken_at);
+    for (taken_at, status) in logs {
+        let closest = expected
+            .iter()
+            .enumerate()
+            .filter(|(i, due)| matched[*i].is_none() && (taken_at - **due).abs() <= MATCH_WINDOW)
+            .min_by_key(|(_, due)| (taken_at - **due).abs());
+        let Some((i, due)) = closest else {
+            continue;
+        };
+        matched[i] = Some(status);
+        if status != DoseStatus::Skipped {
+            lateness.push((taken_at - *due).num_minutes().max(0));
+        }
+    }
+
+    let logged_doses = matched
+        .iter()
+        .filter(|status| matches!(status, Some(DoseStatus::Taken | DoseStatus::Late)))
+        .count();
+    let skipped_doses = matched
+        .iter()
+        .filter(|status| matches!(status, Some(DoseStatus::Skipped)))
+        .count();
+
+    let mut longest_missed_streak = 0;
+    let mut current_missed_streak = 0;
+    for status in &matched {
+        if matches!(status, None | Some(DoseStatus::Skipped)) {
+            current_missed_streak += 1;
+            longest_missed_streak = longest_missed_streak.max(current_missed_streak);
+        } else {
+            current_missed_streak = 0;
+        }
+    }
+
+    lateness.sort_unstable();
+    #[allow(clippy::cast_precision_loss)]
+    AdherenceStats {
+        expected_doses: expected.len(),
+        logged_doses,
+        skipped_doses,
+        missed_doses: expected.len() - logged_doses,
+        adherence_percent: (!expected.is_empty())
+            .then(|| logged_doses as f64 / expected.len() as f64 * 100.0),
+        median_lateness_minutes: match lateness.len() {
+            0 => None,
+            len if len % 2 == 0 => {
+                let (lower, upper) = (lateness[len / 2 - 1], lateness[len / 2]);
+                Some(lower + (upper - lower) / 2)
+            }
+            len => Some(lateness[len / 2]),
+        },
+        longest_missed_streak,
+        current_missed_streak,
+    }
+}
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn adherence(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Query(query): Query<AdherenceQuery>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to view medication adherence"),
+        )
+            .into_response();
+    };
+
+    let patient_id = query.patient_id.unwrap_or(auth.id);
+    if let Err(response) =
+        check_access(&db, auth.id, patient_id, CaregiverScope::ViewMedications).await
+    {
+        return response;
+    }
+
+    let from = query.from.and_time(NaiveTime::MIN).and_utc();
+    let Some(to) = query
+        .to
+        .and_time(NaiveTime::MIN)
+        .and_utc()
+        .checked_add_days(Days::new(1))
+        .map(|end| end - Duration::milliseconds(1))
+    else {
+        return (StatusCode::BAD_REQUEST, String::from("Invalid date range")).into_response();
+    };
+    if to < from {
+        return (StatusCode::BAD_REQUEST, String::from("Invalid date range")).into_response();
+    }
+    if (query.to - query.from).num_days() >= MAX_RANGE_DAYS {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("The date range can be at most {MAX_RANGE_DAYS} days"),
+        )
+            .into_response();
+    }
+
+    match get_adherence(&db, patient_id, from, to).await {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Works out how well a patient kept to each of their medication schedules between `from` and
+/// `to`
+pub(crate) async fn get_adherence(
+    db: &Database,
+    patient_id: ObjectId,
+    from: chrono::DateTime<Utc>,
+    to: chrono::DateTime<Utc>,
+) -> mongodb::error::Result<Vec<MedicationAdherence>> {
+    // Doses that aren't due yet can't have been missed
+    let to = to.min(Utc::now());
+
+    let medications = get_users_medications(patient_id, true, db).await?;
+    let dose_logs = db
+        .collection::<DoseLog>("dose_logs")
+        .find(doc! {
+          "user_id": patient_id,
+          "taken_at": {
+            "$gte": DateTime::from_chrono(from - MATCH_WINDOW),
+            "$lte": DateTime::from_chrono(to + MATCH_WINDOW),
+          }
+        })
+        .await?
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    let report = medications
+        .into_iter()
+        .map(|medication| {
+            let expected = medication
+                .schedule
+                .as_ref()
+                .map(|schedule| schedule.doses_between(from, to))
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|due| medication.was_active_at(*due))
+                .collect::<Vec<_>>();
+            let logs = dose_logs
+                .iter()
+                .filter(|log| log.medication_id == medication.id)
+                .map(|log| (log.taken_at.to_chrono(), log.status))
+                .collect::<Vec<_>>();
+            MedicationAdherence {
+                medication_id: medication.id,
+                medication_name: medication.medication_name,
+                stats: compute_adherence(&expected, &logs),
+            }
+        })
+        .collect::<Vec<_>>();
+    Ok(report)
+}
+
+#[test]
+fn adherence_counts_missed_and_late_doses() {
+    let start = chrono::DateTime::parse_from_rfc3339("2024-06-01T08:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    let expected = (0..4)
+        .map(|i| start + Duration::hours(4 * i))
+        .collect::<Vec<_>>();
+    let logs = vec![
+        (expected[0] + Duration::minutes(10), DoseStatus::Taken),
+        (expected[1] + Duration::minutes(50), DoseStatus::Late),
+        (expected[3], DoseStatus::Skipped),
+    ];
+
+    let stats = compute_adherence(&expected, &logs);
+
+    assert_eq!(stats.expected_doses, 4);
+    assert_eq!(stats.logged_doses, 2);
+    assert_eq!(stats.skipped_doses, 1);
+    assert_eq!(stats.missed_doses, 2);
+    assert_eq!(stats.adherence_percent, Some(50.0));
+    assert_eq!(stats.median_lateness_minutes, Some(30));
+    assert_eq!(stats.longest_missed_streak, 2);
+    assert_eq!(stats.current_missed_streak, 2);
+}
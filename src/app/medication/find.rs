@@ -1,15 +1,22 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::{IntoResponse, Response},
     Json,
 };
-use futures::StreamExt;
-use mongodb::{bson::doc, Database};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, Bson},
+    Database,
+};
 
 use crate::app::{
     auth::middleware::Auth,
-    models::{dto::medication::FindPath, MedicationTrackerEntry},
+    caregiver::access::check_access,
+    models::{
+        dto::medication::{FindAllQuery, FindPath, PatientMedicationPath, PatientPath},
+        CaregiverScope, MedicationTrackerEntry,
+    },
 };
 
 #[tracing::instrument]
@@ -27,11 +34,48 @@ pub async fn find_medication(
             .into_response();
     };
 
+    find_users_medication(&db, auth.id, path.medication_id).await
+}
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn find_patient_medication(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<PatientMedicationPath>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to access medication tracker entries"),
+        )
+            .into_response();
+    };
+
+    if let Err(response) = check_access(
+        &db,
+        auth.id,
+        path.patient_id,
+        CaregiverScope::ViewMedications,
+    )
+    .await
+    {
+        return response;
+    }
+
+    find_users_medication(&db, path.patient_id, path.medication_id).await
+}
+
+async fn find_users_medication(
+    db: &Database,
+    user_id: ObjectId,
+    medication_id: ObjectId,
+) -> Response {
     let result = db
         .collection::<MedicationTrackerEntry>("medications")
         .find_one(doc! {
-          "_id": path.medication_id,
-          "user_id": auth.id
+          "_id": medication_id,
+          "user_id": user_id
         })
         .await;
     match result {
@@ -45,7 +89,33 @@ pub async fn find_medication(
 
 #[tracing::instrument]
 #[axum::debug_handler]
-pub async fn find_all_medications(State(db): State<Database>, Auth(auth): Auth) -> Response {
+pub async fn find_all_medications(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Query(query): Query<FindAllQuery>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to access medication tracker entries"),
+        )
+            .into_response();
+    };
+
+    match get_users_medications(auth.id, query.include_stopped, &db).await {
+        Ok(medications) => (StatusCode::OK, Json(medications)).into_response(),
+        Err(..) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn find_all_patient_medications(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<PatientPath>,
+    Query(query): Query<FindAllQuery>,
+) -> Response {
     let Some(auth) = auth else {
         return (
             StatusCode::UNAUTHORIZED,
@@ -54,24 +124,44 @@ pub async fn find_all_medications(State(db): State<Database>, Auth(auth): Auth)
             .into_response();
     };
 
+    if let Err(response) = check_access(
+        &db,
+        auth.id,
+        path.patient_id,
+        CaregiverScope::ViewMedications,
+    )
+    .await
+    {
+        return response;
+    }
+
+    match get_users_medications(path.patient_id, query.include_stopped, &db).await {
+        Ok(medications) => (StatusCode::OK, Json(medications)).into_response(),
+        Err(..) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+pub(crate) async fn get_users_medications(
+    user_id: ObjectId,
+    include_stopped: bool,
+    db: &Database,
+) -> Result<Vec<MedicationTrackerEntry>, mongodb::error::Error> {
+    let mut filter = doc! {
+      "user_id": user_id
+    };
+    if !include_stopped {
+        filter.insert("stopped_at", Bson::Null);
+    }
     let result = db
         .collection::<MedicationTrackerEntry>("medications")
-        .find(doc! {
-          "user_id": auth.id
-        })
+        .find(filter)
         .await;
 
     match result {
-        Ok(mut data) => {
-            let mut medications: Vec<MedicationTrackerEntry> = Vec::new();
-            while let Some(Ok(medication)) = data.next().await {
-                medications.push(medication);
-            }
-            (StatusCode::OK, Json(medications)).into_response()
-        }
+        Ok(data) => data.try_collect().await,
         Err(e) => {
             tracing::error!(error = %e, "Error occurred while querying database");
-            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            Err(e)
         }
     }
 }
@@ -0,0 +1,74 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use mongodb::{bson::oid::ObjectId, Database};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{
+    auth::middleware::Auth,
+    caregiver::access::check_access,
+    medication::find::get_users_medications,
+    models::{dto::medication::HistoryQuery, CaregiverScope, MedicationEvent},
+};
+
+#[derive(Serialize, Deserialize)]
+struct MedicationEventWithMedication {
+    medication_id: ObjectId,
+    medication_name: String,
+    event: MedicationEvent,
+}
+
+/// Lists every change made to a user's medications, newest first
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn history(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Query(query): Query<HistoryQuery>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to view medication history"),
+        )
+            .into_response();
+    };
+
+    let patient_id = query.patient_id.unwrap_or(auth.id);
+    if let Err(response) =
+        check_access(&db, auth.id, patient_id, CaregiverScope::ViewMedications).await
+    {
+        return response;
+    }
+
+    let Ok(medications) = get_users_medications(patient_id, true, &db).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+
+    let mut res = medications
+        .into_iter()
+        .filter(|medication| {
+            query
+                .medication_id
+                .is_none_or(|medication_id| medication.id == medication_id)
+        })
+        .flat_map(|medication| {
+            let medication_id = medication.id;
+            let medication_name = medication.medication_name;
+            medication
+                .events
+                .into_iter()
+                .map(move |event| MedicationEventWithMedication {
+                    medication_id,
+                    medication_name: medication_name.clone(),
+                    event,
+                })
+        })
+        .collect::<Vec<_>>();
+    res.sort_by_key(|entry| std::cmp::Reverse(entry.event.happened_at()));
+
+    (StatusCode::OK, Json(res)).into_response()
+}
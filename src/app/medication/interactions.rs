@@ -0,0 +1,239 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use mongodb::{
+    bson::{doc, oid::ObjectId},
+    Database,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{
+    auth::middleware::Auth,
+    caregiver::access::check_access,
+    medication::find::get_users_medications,
+    models::{dto::medication::PatientQuery, CaregiverScope, MedicationTrackerEntry},
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Moderate,
+    Major,
+    Contraindicated,
+}
+
+#[derive(Deserialize)]
+struct InteractionRule {
+    between: [String; 2],
+    severity: Severity,
+    description: String,
+}
+
+/// A drug group that is a problem in Parkinson's by itself, regardless of what it is taken with
+#[derive(Deserialize)]
+struct ContraindicationRule {
+    group: String,
+    severity: Severity,
+    description: String,
+}
+
+#[derive(Deserialize)]
+struct Dataset {
+    /// Lowercase generic and brand names making up each group of drugs
+    groups: HashMap<String, Vec<String>>,
+    interactions: Vec<InteractionRule>,
+    contraindications: Vec<ContraindicationRule>,
+}
+
+/// # Panics
+/// Panics if the bundled dataset is invalid, which `interaction_dataset_is_valid` checks for
+fn dataset() -> &'static Dataset {
+    static DATASET: OnceLock<Dataset> = OnceLock::new();
+    DATASET.get_or_init(|| {
+        serde_json::from_str(include_str!("interactions.json"))
+            .expect("bundled interaction dataset should be valid")
+    })
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct InteractionWarning {
+    pub severity: Severity,
+    /// The medication tracker entries involved, one for a contraindication or two for an interaction
+    pub medication_ids: Vec<ObjectId>,
+    pub medication_names: Vec<String>,
+    pub description: String,
+}
+
+fn in_group(medication_name: &str, group: &str) -> bool {
+    let words = medication_name
+        .to_lowercase()
+        .split(|c: char| !(c.is_alphanumeric() || c == '\'' || c == '-'))
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let words = format!(" {words} ");
+    dataset().groups.get(group).is_some_and(|aliases| {
+        aliases
+            .iter()
+            .any(|alias| words.contains(&format!(" {alias} ")))
+    })
+}
+
+/// Checks a list of medications against the bundled interaction dataset, most severe warnings first
+#[must_use]
+pub fn check_interactions(medications: &[MedicationTrackerEntry]) -> Vec<InteractionWarning> {
+    let dataset = dataset();
+    let mut warnings = Vec::new();
+
+    for rule in &dataset.contraindications {
+        for medication in medications
+            .iter()
+            .filter(|medication| in_group(&medication.medication_name, &rule.group))
+        {
+            warnings.push(InteractionWarning {
+                severity: rule.severity,
+                medication_ids: vec![medication.id],
+                medication_names: vec![medication.medication_name.clone()],
+                description: rule.description.clone(),
+            });
+        }
+    }
+
+    for rule in &dataset.interactions {
+        let [first_group, second_group] = &rule.between;
+        for first in medications
+            .iter()
+            .filter(|medication| in_group(&medication.medication_name, first_group))
+        {
+            for second in medications.iter().filter(|medication| {
+                medication.id != first.id && in_group(&medication.medication_name, second_group)
+            }) {
+                let already_warned = warnings.iter().any(|warning: &InteractionWarning| {
+                    warning.description == rule.description
+                        && warning.medication_ids.contains(&first.id)
+                        && warning.medication_ids.contains(&second.id)
+                });
+                if !already_warned {
+                    warnings.push(InteractionWarning {
+                        severity: rule.severity,
+                        medication_ids: vec![first.id, second.id],
+                        medication_names: vec![
+                            first.medication_name.clone(),
+                            second.medication_name.clone(),
+                        ],
+                        description: rule.description.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    warnings.sort_by_key(|warning| std::cmp::Reverse(warning.severity));
+    warnings
+}
+
+/// Finds the interaction warnings involving one of a user's medications, to return after it is added or updated
+///
+/// The warnings are advisory, so if the medication list can't be read this logs the error and
+/// returns no warnings rather than failing the request.
+pub async fn warnings_for_medication(
+    db: &Database,
+    user_id: ObjectId,
+    medication_id: ObjectId,
+) -> Vec<InteractionWarning> {
+    let medications = get_users_medications(user_id, false, db).await;
+    match medications {
+        Ok(medications) => check_interactions(&medications)
+            .into_iter()
+            .filter(|warning| warning.medication_ids.contains(&medication_id))
+            .collect(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while checking medication interactions");
+            Vec::new()
+        }
+    }
+}
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn find_interactions(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Query(query): Query<PatientQuery>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to check medication interactions"),
+        )
+            .into_response();
+    };
+
+    let patient_id = query.patient_id.unwrap_or(auth.id);
+    if let Err(response) =
+        check_access(&db, auth.id, patient_id, CaregiverScope::ViewMedications).await
+    {
+        return response;
+    }
+
+    let medications = get_users_medications(patient_id, false, &db).await;
+    match medications {
+        Ok(medications) => (StatusCode::OK, Json(check_interactions(&medications))).into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[test]
+fn interaction_dataset_is_valid() {
+    let dataset = dataset();
+    for rule in &dataset.interactions {
+        for group in &rule.between {
+            assert!(dataset.groups.contains_key(group), "unknown group {group}");
+        }
+    }
+    for rule in &dataset.contraindications {
+        assert!(
+            dataset.groups.contains_key(&rule.group),
+            "unknown group {}",
+            rule.group
+        );
+    }
+}
+
+#[test]
+fn warns_about_interactions_and_contraindications() {
+    let user_id = ObjectId::new();
+    let medication = |name: &str| {
+        MedicationTrackerEntry::from(
+            ObjectId::new(),
+            user_id,
+            user_id,
+            String::from(name),
+            String::from("1mg"),
+            String::from("daily"),
+            None,
+        )
+    };
+    let medications = [
+        medication("Rasagiline"),
+        medication("Sertraline"),
+        medication("Metoclopramide"),
+        medication("Spironolactone"),
+    ];
+
+    let warnings = check_interactions(&medications);
+
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(warnings[0].medication_ids, vec![medications[2].id]);
+    assert_eq!(
+        warnings[1].medication_ids,
+        vec![medications[0].id, medications[1].id]
+    );
+}
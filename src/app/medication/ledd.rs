@@ -0,0 +1,505 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use mongodb::{
+    bson::{doc, oid::ObjectId},
+    Database,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{
+    auth::middleware::Auth,
+    caregiver::access::check_access,
+    medication::find::get_users_medications,
+    models::{dto::medication::PatientQuery, CaregiverScope, MedicationTrackerEntry},
+};
+
+/// How a drug's daily dose is converted to a levodopa equivalent dose
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Conversion {
+    /// Multiply the daily dose in mg by this factor
+    Dose(f64),
+    /// Multiply the patient's total immediate release levodopa LEDD by this factor, as for COMT
+    /// inhibitors which only extend the effect of levodopa taken alongside them
+    Levodopa(f64),
+}
+
+struct Drug {
+    name: &'static str,
+    /// Lowercase generic and brand names that identify the drug in a medication name
+    aliases: &'static [&'static str],
+    conversion: Conversion,
+}
+
+/// Conversion factors from Tomlinson et al. (2010) "Systematic review of levodopa dose
+/// equivalency reporting in Parkinson's disease", with the opicapone and levodopa/carbidopa
+/// intestinal gel factors from Jost et al. (2023).
+///
+/// Entries are checked in order, so combination and modified release products must come before
+/// plain levodopa. Decarboxylase inhibitors (carbidopa and benserazide) have no LEDD of their own,
+/// so they are only counted as part of a levodopa product.
+const DRUGS: &[Drug] = &[
+    Drug {
+        name: "levodopa/carbidopa/entacapone",
+        aliases: &["stalevo"],
+        conversion: Conversion::Dose(1.33),
+    },
+    Drug {
+        name: "levodopa/carbidopa intestinal gel",
+        aliases: &["duodopa", "intestinal gel"],
+        conversion: Conversion::Dose(1.11),
+    },
+    Drug {
+        name: "levodopa controlled release",
+        aliases: &[
+            "sinemet cr",
+            "madopar cr",
+            "madopar hbs",
+            "controlled release",
+            "modified release",
+            "levodopa cr",
+            "levodopa mr",
+            "co-careldopa mr",
+            "co-beneldopa mr",
+        ],
+        conversion: Conversion::Dose(0.75),
+    },
+    Drug {
+        name: "levodopa",
+        aliases: &[
+            "levodopa",
+            "l-dopa",
+            "sinemet",
+            "madopar",
+            "co-careldopa",
+            "co-beneldopa",
+        ],
+        conversion: Conversion::Dose(1.0),
+    },
+    Drug {
+        name: "entacapone",
+        aliases: &["entacapone", "comtan", "comtess"],
+        conversion: Conversion::Levodopa(0.33),
+    },
+    Drug {
+        name: "tolcapone",
+        aliases: &["tolcapone", "tasmar"],
+        conversion: Conversion::Levodopa(0.5),
+    },
+    Drug {
+        name: "opicapone",
+        aliases: &["opicapone", "ongentys"],
+        conversion: Conversion::Levodopa(0.5),
+    },
+    Drug {
+        name: "pramipexole",
+        aliases: &["pramipexole", "mirapex", "mirapexin", "sifrol"],
+        conversion: Conversion::Dose(100.0),
+    },
+    Drug {
+        name: "ropinirole",
+        aliases: &["ropinirole", "requip", "adartrel"],
+        conversion: Conversion::Dose(20.0),
+    },
+    Drug {
+        name: "rotigotine",
+        aliases: &["rotigotine", "neupro"],
+        conversion: Conversion::Dose(30.0),
+    },
+    Drug {
+        name: "apomorphine",
+        aliases: &["apomorphine", "apokyn", "apo-go"],
+        conversion: Conversion::Dose(10.0),
+    },
+    Drug {
+        name: "bromocriptine",
+        aliases: &["bromocriptine", "parlodel"],
+        conversion: Conversion::Dose(10.0),
+    },
+    Drug {
+        name: "cabergoline",
+        aliases: &["cabergoline", "dostinex", "cabaser"],
+        conversion: Conversion::Dose(80.0),
+    },
+    Drug {
+        name: "selegiline (sublingual)",
+        aliases: &[
+            "zelapar",
+            "selegiline sublingual",
+            "selegiline oral lyophilisate",
+        ],
+        conversion: Conversion::Dose(80.0),
+    },
+    Drug {
+        name: "selegiline",
+        aliases: &["selegiline", "eldepryl", "deprenyl"],
+        conversion: Conversion::Dose(10.0),
+    },
+    Drug {
+        name: "rasagiline",
+        aliases: &["rasagiline", "azilect"],
+        conversion: Conversion::Dose(100.0),
+    },
+    Drug {
+        name: "amantadine",
+        aliases: &["amantadine", "symmetrel", "gocovri"],
+        conversion: Conversion::Dose(1.0),
+    },
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LeddEntry {
+    pub medication_id: ObjectId,
+    pub medication_name: String,
+    /// The drug from the conversion table this entry was interpreted as
+    pub drug: String,
+    pub dose_mg: f64,
+    pub doses_per_day: u32,
+    pub ledd: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UninterpretedEntry {
+    pub medication_id: ObjectId,
+    pub medication_name: String,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LeddReport {
+    pub total_ledd: f64,
+    pub entries: Vec<LeddEntry>,
+    /// Entries that could not be converted, so are missing from `total_ledd`
+    pub uninterpreted: Vec<UninterpretedEntry>,
+}
+
+fn find_drug(medication_name: &str) -> Option<&'static Drug> {
+    let medication_name = medication_name.to_lowercase();
+    DRUGS.iter().find(|drug| {
+        drug.aliases
+            .iter()
+            .any(|alias| medication_name.contains(alias))
+    })
+}
+
+/// Splits text into lowercase words and numbers
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    for c in text.to_lowercase().chars() {
+        let continues = current.chars().last().is_some_and(|last| {
+            (last.is_ascii_digit() || last == '.') == (c.is_ascii_digit() || c == '.')
+        });
+        if c.is_alphanumeric() || c == '.' || c == 'µ' {
+            if !current.is_empty() && !continues {
+                tokens.push(std::mem::take(&mut current));
+            }
+            current.push(c);
+        } else {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            if c == '/' || c == '×' {
+                tokens.push(c.to_string());
+            }
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+/// Parses the amount of the active drug in a single dose, in mg
+///
+/// Combination strengths like `25/100` (carbidopa/levodopa) take the larger number, as the
+/// decarboxylase inhibitor is always the smaller component. Multipliers like `2 x 100mg` are
+/// applied.
+fn parse_dose_mg(dose: &str) -> Option<f64> {
+    let tokens = tokenize(dose);
+    let mut amount: Option<f64> = None;
+    let mut multiplier = 1.0;
+    let mut unit_scale = 1.0;
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i].as_str();
+        if let Ok(number) = token.parse::<f64>() {
+            let next = tokens.get(i + 1).map(String::as_str);
+            if matches!(next, Some("x" | "×")) {
+                multiplier *= number;
+                i += 2;
+                continue;
+            }
+            if next == Some("/") {
+                if let Some(Ok(other)) = tokens.get(i + 2).map(|other| other.parse::<f64>()) {
+                    amount.get_or_insert(number.max(other));
+                    i += 3;
+                    continue;
+                }
+            }
+            amount.get_or_insert(number);
+        } else {
+            match token {
+                "mcg" | "µg" | "ug" | "micrograms" => unit_scale = 0.001,
+                "g" | "grams" => unit_scale = 1000.0,
+                _ => {}
+            }
+        }
+        i += 1;
+    }
+    amount.map(|amount| amount * multiplier * unit_scale)
+}
+
+/// Parses how many doses are taken per day from free text like `tds`, `3 times a day`,
+/// `every 4 hours` or `8am, 12pm, 6pm`
+fn parse_doses_per_day(timing: &str) -> Option<u32> {
+    let tokens = tokenize(timing);
+    let has = |word: &str| tokens.iter().any(|token| token == word);
+    let number_before = |word: &str| {
+        tokens.windows(2).find_map(|pair| {
+            (pair[1] == word)
+                .then(|| pair[0].parse::<u32>().ok())
+                .flatten()
+        })
+    };
+
+    if let Some((interval, unit)) = tokens.iter().enumerate().find_map(|(i, token)| {
+        let interval = tokens.get(i + 1)?.parse::<u32>().ok()?;
+        (token == "every").then(|| (interval, tokens.get(i + 2).map(String::as_str)))
+    }) {
+        // Anything other than hours, like `every 2 days`, isn't a whole number of doses a day
+        return match unit {
+            Some("h" | "hr" | "hrs" | "hour" | "hours") if interval > 0 && interval <= 24 => {
+                Some(24 / interval)
+            }
+            _ => None,
+        };
+    }
+    if let Some(count) = number_before("times").or_else(|| number_before("x")) {
+        return Some(count);
+    }
+    if has("qds") || has("qid") || (has("four") && has("times")) {
+        return Some(4);
+    }
+    if has("tds") || has("tid") || has("thrice") || (has("three") && has("times")) {
+        return Some(3);
+    }
+    if has("bd") || has("bid") || has("twice") || (has("two") && has("times")) {
+        return Some(2);
+    }
+
+    let listed_times = timing
+        .split([',', ';', '&'])
+        .flat_map(|part| part.split(" and "))
+        .filter(|part| part.chars().any(|c| c.is_ascii_digit()))
+        .count();
+    if listed_times > 1 {
+        return u32::try_from(listed_times).ok();
+    }
+
+    if has("od")
+        || has("once")
+        || has("daily")
+        || has("nocte")
+        || has("mane")
+        || has("night")
+        || has("morning")
+        || has("patch")
+        || listed_times == 1
+    {
+        return Some(1);
+    }
+    None
+}
+
+/// Works out the levodopa equivalent daily dose of each medication, and the total
+#[must_use]
+pub fn compute_ledd(medications: &[MedicationTrackerEntry]) -> LeddReport {
+    let mut entries = Vec::new();
+    let mut uninterpreted = Vec::new();
+    let mut adjuncts = Vec::new();
+
+    for medication in medications {
+        let reject = |reason: &str| UninterpretedEntry {
+            medication_id: medication.id,
+            medication_name: medication.medication_name.clone(),
+            reason: String::from(reason),
+        };
+        let Some(drug) = find_drug(&medication.medication_name) else {
+            uninterpreted.push(reject("Not a recognised Parkinson's medication"));
+            continue;
+        };
+        let Some(dose_mg) = parse_dose_mg(&medication.dose) else {
+            uninterpreted.push(reject("Could not read the dose"));
+            continue;
+        };
+        let doses_per_day = match &medication.schedule {
+            Some(schedule) if !schedule.times.is_empty() => {
+                u32::try_from(schedule.times.len()).ok()
+            }
+            _ => parse_doses_per_day(&medication.timing),
+        };
+        let Some(doses_per_day) = doses_per_day else {
+            uninterpreted.push(reject("Could not work out how many doses are taken a day"));
+            continue;
+        };
+        let entry = LeddEntry {
+            medication_id: medication.id,
+            medication_name: medication.medication_name.clone(),
+            drug: String::from(drug.name),
+            dose_mg,
+            doses_per_day,
+            ledd: 0.0,
+        };
+        match drug.conversion {
+            Conversion::Dose(factor) => entries.push(LeddEntry {
+                ledd: dose_mg * f64::from(doses_per_day) * factor,
+                ..entry
+            }),
+            Conversion::Levodopa(factor) => adjuncts.push((entry, factor)),
+        }
+    }
+
+    let levodopa_ledd = entries
+        .iter()
+        .filter(|entry| entry.drug == "levodopa")
+        .map(|entry| entry.ledd)
+        .sum::<f64>();
+    for (entry, factor) in adjuncts {
+        entries.push(LeddEntry {
+            ledd: levodopa_ledd * factor,
+            ..entry
+        });
+    }
+
+    LeddReport {
+        total_ledd: entries.iter().map(|entry| entry.ledd).sum(),
+        entries,
+        uninterpreted,
+    }
+}
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn ledd(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Query(query): Query<PatientQuery>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to calculate LEDD"),
+        )
+            .into_response();
+    };
+
+    let patient_id = query.patient_id.unwrap_or(auth.id);
+    if let Err(response) =
+        check_access(&db, auth.id, patient_id, CaregiverScope::ViewMedications).await
+    {
+        return response;
+    }
+
+    let medications = get_users_medications(patient_id, false, &db).await;
+    match medications {
+        Ok(medications) => (StatusCode::OK, Json(compute_ledd(&medications))).into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[test]
+fn parses_doses_and_timings() {
+    assert_eq!(parse_dose_mg("25/100mg"), Some(100.0));
+    assert_eq!(parse_dose_mg("2 x 0.25 mg"), Some(0.5));
+    assert_eq!(parse_dose_mg("500 micrograms"), Some(0.5));
+    assert_eq!(parse_dose_mg("one tablet"), None);
+
+    assert_eq!(parse_doses_per_day("TDS"), Some(3));
+    assert_eq!(parse_doses_per_day("3 times a day"), Some(3));
+    assert_eq!(parse_doses_per_day("every 4 hours"), Some(6));
+    assert_eq!(parse_doses_per_day("every 8h"), Some(3));
+    assert_eq!(parse_doses_per_day("every 2 days"), None);
+    assert_eq!(parse_doses_per_day("every 7 days"), None);
+    assert_eq!(parse_doses_per_day("every 1 week"), None);
+    assert_eq!(parse_doses_per_day("8am, 12pm, 4pm and 8pm"), Some(4));
+    assert_eq!(parse_doses_per_day("once daily"), Some(1));
+    assert_eq!(parse_doses_per_day("as needed"), None);
+}
+
+#[test]
+fn computes_total_ledd() {
+    let user_id = ObjectId::new();
+    let medication = |name: &str, dose: &str, timing: &str| {
+        MedicationTrackerEntry::from(
+            ObjectId::new(),
+            user_id,
+            user_id,
+            String::from(name),
+            String::from(dose),
+            String::from(timing),
+            None,
+        )
+    };
+    let report = compute_ledd(&[
+        medication("Sinemet", "25/100mg", "tds"),
+        medication("Entacapone", "200mg", "tds"),
+        medication("Pramipexole", "0.5mg", "tds"),
+        medication("Sinemet CR", "50/200mg", "at night"),
+        medication("Paracetamol", "1g", "qds"),
+    ]);
+
+    let ledd_of = |drug: &str| {
+        report
+            .entries
+            .iter()
+            .find(|entry| entry.drug == drug)
+            .map(|entry| entry.ledd)
+    };
+    assert_eq!(ledd_of("levodopa"), Some(300.0));
+    assert_eq!(ledd_of("levodopa controlled release"), Some(150.0));
+    assert_eq!(ledd_of("pramipexole"), Some(150.0));
+    assert!((ledd_of("entacapone").unwrap() - 99.0).abs() < 1e-9);
+    assert!((report.total_ledd - 699.0).abs() < 1e-9);
+    assert_eq!(report.uninterpreted.len(), 1);
+    assert_eq!(report.uninterpreted[0].medication_name, "Paracetamol");
+}
+
+#[test]
+fn carbidopa_alone_is_not_levodopa() {
+    let user_id = ObjectId::new();
+    let report = compute_ledd(&[
+        MedicationTrackerEntry::from(
+            ObjectId::new(),
+            user_id,
+            user_id,
+            String::from("Lodosyn"),
+            String::from("25mg"),
+            String::from("tds"),
+            None,
+        ),
+        MedicationTrackerEntry::from(
+            ObjectId::new(),
+            user_id,
+            user_id,
+            String::from("Carbidopa"),
+            String::from("25mg"),
+            String::from("tds"),
+            None,
+        ),
+    ]);
+
+    assert!(report.entries.is_empty());
+    assert!(report.total_ledd.abs() < f64::EPSILON);
+    assert_eq!(report.uninterpreted.len(), 2);
+    assert_eq!(
+        find_drug("Carbidopa/Levodopa").map(|drug| drug.name),
+        Some("levodopa")
+    );
+}
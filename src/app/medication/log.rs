@@ -0,0 +1,140 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Days, NaiveTime};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, DateTime},
+    Database,
+};
+use serde_json::json;
+
+use crate::app::{
+    auth::middleware::Auth,
+    medication::stock::take_from_stock,
+    models::{
+        dto::medication::{DoseLogQuery, LogDosePayload, LogPath},
+        DoseLog, DoseStatus, MedicationTrackerEntry,
+    },
+};
+
+// TODO!: input validation wrt. note length, etc
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn log_dose(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<LogPath>,
+    Json(payload): Json<LogDosePayload>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to log a dose"),
+        )
+            .into_response();
+    };
+
+    let medication = match db
+        .collection::<MedicationTrackerEntry>("medications")
+        .find_one(doc! { "_id": path.medication_id, "user_id": auth.id })
+        .await
+    {
+        Ok(Some(medication)) => medication,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                String::from("Could not find medication"),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let id = ObjectId::new();
+    let now = DateTime::now();
+    let status = payload.status;
+    let dose_log = DoseLog {
+        id,
+        medication_id: path.medication_id,
+        user_id: auth.id,
+        status,
+        taken_at: payload.taken_at.map_or(now, DateTime::from_chrono),
+        note: payload.note,
+        logged_by: auth.id,
+        logged_at: now,
+    };
+    let result = db
+        .collection::<DoseLog>("dose_logs")
+        .insert_one(dose_log)
+        .await;
+    match result {
+        Ok(..) => {
+            if status != DoseStatus::Skipped {
+                take_from_stock(&db, &medication).await;
+            }
+            (StatusCode::OK, Json(json! ({ "created_id": id }))).into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn find_dose_logs(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Query(query): Query<DoseLogQuery>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to access dose logs"),
+        )
+            .into_response();
+    };
+
+    let day_start = query.date.and_time(NaiveTime::MIN).and_utc();
+    let Some(day_end) = day_start.checked_add_days(Days::new(1)) else {
+        return (StatusCode::BAD_REQUEST, String::from("Invalid date")).into_response();
+    };
+
+    let mut filter = doc! {
+      "user_id": auth.id,
+      "taken_at": {
+        "$gte": DateTime::from_chrono(day_start),
+        "$lt": DateTime::from_chrono(day_end),
+      }
+    };
+    if let Some(medication_id) = query.medication_id {
+        filter.insert("medication_id", medication_id);
+    }
+
+    let result = db
+        .collection::<DoseLog>("dose_logs")
+        .find(filter)
+        .sort(doc! { "taken_at": 1 })
+        .await;
+    match result {
+        Ok(cursor) => match cursor.try_collect::<Vec<DoseLog>>().await {
+            Ok(dose_logs) => (StatusCode::OK, Json(dose_logs)).into_response(),
+            Err(e) => {
+                tracing::error!(error = %e, "Error occurred while reading dose logs");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        },
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
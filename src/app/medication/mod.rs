@@ -1,16 +1,32 @@
 mod add;
-mod find;
+pub(crate) mod adherence;
+pub(crate) mod find;
+mod history;
+mod interactions;
+mod ledd;
+mod log;
 mod remove;
+pub(crate) mod stock;
 mod update;
 
-use add::add_medication;
+use add::{add_medication, add_patient_medication};
+use adherence::adherence;
 use axum::{
     routing::{get, post},
     Router,
 };
-use find::{find_all_medications, find_medication};
-use remove::remove_medication;
-use update::update_medication;
+use find::{
+    find_all_medications, find_all_patient_medications, find_medication, find_patient_medication,
+};
+use history::history;
+use interactions::find_interactions;
+use ledd::ledd;
+use log::{find_dose_logs, log_dose};
+use remove::{
+    remove_medication, remove_patient_medication, restart_medication, restart_patient_medication,
+};
+use stock::{find_stock, refill, set_stock};
+use update::{update_medication, update_patient_medication};
 
 use super::AppState;
 
@@ -21,4 +37,35 @@ pub fn router() -> Router<AppState> {
         .route("/add", post(add_medication))
         .route("/update/:medication_id", post(update_medication))
         .route("/remove/:medication_id", post(remove_medication))
+        .route("/restart/:medication_id", post(restart_medication))
+        .route("/history", get(history))
+        .route("/adherence", get(adherence))
+        .route(
+            "/patient/:patient_id/find/:medication_id",
+            get(find_patient_medication),
+        )
+        .route(
+            "/patient/:patient_id/find",
+            get(find_all_patient_medications),
+        )
+        .route("/patient/:patient_id/add", post(add_patient_medication))
+        .route(
+            "/patient/:patient_id/update/:medication_id",
+            post(update_patient_medication),
+        )
+        .route(
+            "/patient/:patient_id/remove/:medication_id",
+            post(remove_patient_medication),
+        )
+        .route(
+            "/patient/:patient_id/restart/:medication_id",
+            post(restart_patient_medication),
+        )
+        .route("/interactions", get(find_interactions))
+        .route("/ledd", get(ledd))
+        .route("/log", get(find_dose_logs))
+        .route("/stock", get(find_stock))
+        .route("/:medication_id/log", post(log_dose))
+        .route("/:medication_id/stock", post(set_stock))
+        .route("/:medication_id/refill", post(refill))
 }
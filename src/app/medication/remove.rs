@@ -3,13 +3,23 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
-use mongodb::{bson::doc, Database};
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson, Bson, DateTime},
+    Database,
+};
 
 use crate::app::{
     auth::middleware::Auth,
-    models::{dto::medication::RemovePath, MedicationTrackerEntry},
+    caregiver::access::check_access,
+    events::{publish_to_patient_circle, LiveEvent},
+    models::{
+        dto::medication::{PatientMedicationPath, RemovePath, RestartPath},
+        CaregiverScope, MedicationEvent, MedicationRestarted, MedicationStopped,
+        MedicationTrackerEntry,
+    },
 };
 
+/// Stops a medication. The entry is kept, with a `Stopped` event, so its history isn't lost
 // TODO!: input validation wrt. string length, etc
 #[tracing::instrument]
 #[axum::debug_handler]
@@ -26,15 +36,144 @@ pub async fn remove_medication(
             .into_response();
     };
 
+    set_medication_stopped(&db, auth.id, auth.id, path.medication_id, true).await
+}
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn remove_patient_medication(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<PatientMedicationPath>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to remove a medication"),
+        )
+            .into_response();
+    };
+
+    if let Err(response) = check_access(
+        &db,
+        auth.id,
+        path.patient_id,
+        CaregiverScope::ManageMedications,
+    )
+    .await
+    {
+        return response;
+    }
+
+    set_medication_stopped(&db, path.patient_id, auth.id, path.medication_id, true).await
+}
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn restart_medication(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<RestartPath>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to restart a medication"),
+        )
+            .into_response();
+    };
+
+    set_medication_stopped(&db, auth.id, auth.id, path.medication_id, false).await
+}
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn restart_patient_medication(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<PatientMedicationPath>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to restart a medication"),
+        )
+            .into_response();
+    };
+
+    if let Err(response) = check_access(
+        &db,
+        auth.id,
+        path.patient_id,
+        CaregiverScope::ManageMedications,
+    )
+    .await
+    {
+        return response;
+    }
+
+    set_medication_stopped(&db, path.patient_id, auth.id, path.medication_id, false).await
+}
+
+async fn set_medication_stopped(
+    db: &Database,
+    user_id: ObjectId,
+    changed_by: ObjectId,
+    medication_id: ObjectId,
+    stopped: bool,
+) -> Response {
+    let now = DateTime::now();
+    let (event, stopped_at, currently_stopped_at) = if stopped {
+        (
+            MedicationEvent::Stopped(MedicationStopped {
+                stopped_by: changed_by,
+                stopped_at: now,
+            }),
+            Bson::DateTime(now),
+            Bson::Null,
+        )
+    } else {
+        (
+            MedicationEvent::Restarted(MedicationRestarted {
+                restarted_by: changed_by,
+                restarted_at: now,
+            }),
+            Bson::Null,
+            Bson::Document(doc! { "$ne": Bson::Null }),
+        )
+    };
+    let Ok(event) = to_bson(&event) else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+
     let result = db
         .collection::<MedicationTrackerEntry>("medications")
-        .delete_one(doc! {"_id": path.medication_id, "user_id": auth.id })
+        .update_one(
+            doc! {
+                "_id": medication_id,
+                "user_id": user_id,
+                "stopped_at": currently_stopped_at
+            },
+            doc! {
+                "$set": { "stopped_at": stopped_at },
+                "$push": { "events": event }
+            },
+        )
         .await;
     match result {
         Ok(result) => {
-            if result.deleted_count == 0 {
+            if result.modified_count == 0 {
                 return StatusCode::BAD_REQUEST.into_response();
             }
+            publish_to_patient_circle(
+                db,
+                user_id,
+                LiveEvent::MedicationChanged {
+                    patient_id: user_id,
+                    medication_id,
+                },
+            )
+            .await;
             StatusCode::OK.into_response()
         }
         Err(e) => {
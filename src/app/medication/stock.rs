@@ -0,0 +1,405 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Duration, TimeDelta, Utc};
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson, DateTime},
+    Database,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{
+    auth::middleware::Auth,
+    caregiver::access::check_access,
+    medication::find::get_users_medications,
+    models::{
+        dto::medication::{PatientQuery, RefillPayload, SetStockPayload, StockPath},
+        CaregiverScope, DoseLog, MedicationStock, MedicationTrackerEntry,
+    },
+};
+
+const DEFAULT_LOW_STOCK_DAYS: u32 = 7;
+
+/// How many days of dose logs to average over for medications without a schedule
+const USAGE_WINDOW_DAYS: i64 = 14;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StockStatus {
+    pub medication_id: ObjectId,
+    pub medication_name: String,
+    pub current_count: f64,
+    /// Average number of units used per day, if it can be worked out
+    pub daily_usage: Option<f64>,
+    pub days_remaining: Option<f64>,
+    pub projected_run_out: Option<DateTime>,
+    pub next_refill_due: Option<DateTime>,
+    pub low_stock: bool,
+}
+
+/// Projects when a medication will run out, using its schedule or, failing that, how many doses
+/// were logged as taken in the last `USAGE_WINDOW_DAYS` days
+#[must_use]
+pub fn stock_status(
+    medication: &MedicationTrackerEntry,
+    recent_doses_taken: usize,
+    now: chrono::DateTime<Utc>,
+) -> Option<StockStatus> {
+    let stock = medication.stock.as_ref()?;
+
+    #[allow(clippy::cast_precision_loss)]
+    let doses_per_day = match &medication.schedule {
+        Some(schedule) if !schedule.times.is_empty() => {
+            let days_per_week = if schedule.days.is_empty() {
+                7
+            } else {
+                schedule.days.len()
+            };
+            schedule.times.len() as f64 * days_per_week as f64 / 7.0
+        }
+        _ => recent_doses_taken as f64 / USAGE_WINDOW_DAYS as f64,
+    };
+    let daily_usage = (doses_per_day > 0.0 && stock.units_per_dose > 0.0)
+        .then_some(doses_per_day * stock.units_per_dose);
+    let days_remaining = daily_usage.map(|usage| (stock.current_count / usage).max(0.0));
+    // Left as `None` if the medication will outlast anything a date can hold
+    #[allow(clippy::cast_possible_truncation)]
+    let projected_run_out = days_remaining
+        .and_then(|days| TimeDelta::try_minutes((days * 24.0 * 60.0) as i64))
+        .and_then(|remaining| now.checked_add_signed(remaining))
+        .map(DateTime::from_chrono);
+
+    Some(StockStatus {
+        medication_id: medication.id,
+        medication_name: medication.medication_name.clone(),
+        current_count: stock.current_count,
+        daily_usage,
+        days_remaining,
+        projected_run_out,
+        next_refill_due: stock.next_refill_due,
+        low_stock: stock.current_count <= 0.0
+            || days_remaining.is_some_and(|days| days <= f64::from(stock.low_stock_days)),
+    })
+}
+
+/// Finds a medication and checks that `actor_id` may manage it
+///
+/// # Errors
+/// Returns the response that should be sent back if the medication can't be found or managed
+async fn find_managed_medication(
+    db: &Database,
+    actor_id: ObjectId,
+    medication_id: ObjectId,
+) -> Result<MedicationTrackerEntry, Response> {
+    let medication = match db
+        .collection::<MedicationTrackerEntry>("medications")
+        .find_one(doc! { "_id": medication_id })
+        .await
+    {
+        Ok(Some(medication)) => medication,
+        Ok(None) => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                String::from("Could not find medication"),
+            )
+                .into_response())
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        }
+    };
+    check_access(
+        db,
+        actor_id,
+        medication.user_id,
+        CaregiverScope::ManageMedications,
+    )
+    .await?;
+    Ok(medication)
+}
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn set_stock(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<StockPath>,
+    Json(payload): Json<SetStockPayload>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to track medication stock"),
+        )
+            .into_response();
+    };
+
+    // NaN passes every comparison, so numbers are also checked for being finite
+    if !payload.current_count.is_finite() || payload.current_count < 0.0 {
+        return (
+            StatusCode::BAD_REQUEST,
+            String::from("Current count can't be negative"),
+        )
+            .into_response();
+    }
+    if payload
+        .units_per_dose
+        .is_some_and(|units| !units.is_finite() || units <= 0.0)
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            String::from("Units per dose must be more than zero"),
+        )
+            .into_response();
+    }
+
+    if let Err(response) = find_managed_medication(&db, auth.id, path.medication_id).await {
+        return response;
+    }
+
+    let stock = MedicationStock {
+        pack_size: payload.pack_size,
+        current_count: payload.current_count,
+        units_per_dose: payload.units_per_dose.unwrap_or(1.0),
+        low_stock_days: payload.low_stock_days.unwrap_or(DEFAULT_LOW_STOCK_DAYS),
+        last_refilled_at: None,
+        next_refill_due: payload.next_refill_due.map(DateTime::from_chrono),
+    };
+    let Ok(stock) = to_bson(&stock) else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+
+    let result = db
+        .collection::<MedicationTrackerEntry>("medications")
+        .update_one(
+            doc! { "_id": path.medication_id },
+            doc! { "$set": { "stock": stock } },
+        )
+        .await;
+    match result {
+        Ok(..) => StatusCode::OK.into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn refill(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<StockPath>,
+    Json(payload): Json<RefillPayload>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to refill a medication"),
+        )
+            .into_response();
+    };
+
+    if !payload.units.is_finite() || payload.units < 0.0 {
+        return (
+            StatusCode::BAD_REQUEST,
+            String::from("Units added can't be negative"),
+        )
+            .into_response();
+    }
+
+    let medication = match find_managed_medication(&db, auth.id, path.medication_id).await {
+        Ok(medication) => medication,
+        Err(response) => return response,
+    };
+    let Some(stock) = medication.stock else {
+        return (
+            StatusCode::BAD_REQUEST,
+            String::from("Stock is not being tracked for this medication"),
+        )
+            .into_response();
+    };
+
+    let added = f64::from(payload.packs) * f64::from(stock.pack_size) + payload.units;
+    let mut set = doc! { "stock.last_refilled_at": DateTime::now() };
+    if let Some(next_refill_due) = payload.next_refill_due {
+        set.insert(
+            "stock.next_refill_due",
+            DateTime::from_chrono(next_refill_due),
+        );
+    }
+
+    let result = db
+        .collection::<MedicationTrackerEntry>("medications")
+        .update_one(
+            doc! { "_id": path.medication_id },
+            doc! {
+                "$inc": { "stock.current_count": added },
+                "$set": set
+            },
+        )
+        .await;
+    match result {
+        Ok(..) => StatusCode::OK.into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Counts down a medication's stock after a dose is logged as taken
+pub(super) async fn take_from_stock(db: &Database, medication: &MedicationTrackerEntry) {
+    let Some(stock) = &medication.stock else {
+        return;
+    };
+    let result = db
+        .collection::<MedicationTrackerEntry>("medications")
+        .update_one(
+            doc! { "_id": medication.id },
+            vec![doc! { "$set": { "stock.current_count": { "$max": [
+                0,
+                { "$subtract": ["$stock.current_count", stock.units_per_dose] }
+            ] } } }],
+        )
+        .await;
+    if let Err(e) = result {
+        tracing::error!(error = %e, "Failed to update medication stock");
+    }
+}
+
+/// Lists the projected stock of each medication being tracked
+pub(crate) async fn get_stock_statuses(
+    db: &Database,
+    patient_id: ObjectId,
+) -> Result<Vec<StockStatus>, mongodb::error::Error> {
+    let medications = get_users_medications(patient_id, false, db).await?;
+    let now = Utc::now();
+    let usage_since = DateTime::from_chrono(now - Duration::days(USAGE_WINDOW_DAYS));
+
+    let mut statuses = Vec::new();
+    for medication in medications
+        .iter()
+        .filter(|medication| medication.stock.is_some())
+    {
+        let recent_doses_taken = db
+            .collection::<DoseLog>("dose_logs")
+            .count_documents(doc! {
+              "medication_id": medication.id,
+              "status": { "$in": ["Taken", "Late"] },
+              "taken_at": { "$gte": usage_since }
+            })
+            .await?;
+        let recent_doses_taken = usize::try_from(recent_doses_taken).unwrap_or(usize::MAX);
+        statuses.extend(stock_status(medication, recent_doses_taken, now));
+    }
+    Ok(statuses)
+}
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn find_stock(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Query(query): Query<PatientQuery>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to view medication stock"),
+        )
+            .into_response();
+    };
+
+    let patient_id = query.patient_id.unwrap_or(auth.id);
+    if let Err(response) =
+        check_access(&db, auth.id, patient_id, CaregiverScope::ViewMedications).await
+    {
+        return response;
+    }
+
+    match get_stock_statuses(&db, patient_id).await {
+        Ok(statuses) => (StatusCode::OK, Json(statuses)).into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[test]
+fn projects_run_out_from_schedule() {
+    let user_id = ObjectId::new();
+    let mut medication = MedicationTrackerEntry::from(
+        ObjectId::new(),
+        user_id,
+        user_id,
+        String::from("Sinemet"),
+        String::from("25/100mg"),
+        String::from("tds"),
+        Some(crate::app::models::MedicationSchedule {
+            times: vec![
+                chrono::NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+                chrono::NaiveTime::from_hms_opt(14, 0, 0).unwrap(),
+                chrono::NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
+            ],
+            ..Default::default()
+        }),
+    );
+    medication.stock = Some(MedicationStock {
+        pack_size: 100,
+        current_count: 15.0,
+        units_per_dose: 1.5,
+        low_stock_days: 7,
+        last_refilled_at: None,
+        next_refill_due: None,
+    });
+    let now = Utc::now();
+
+    let status = stock_status(&medication, 0, now).unwrap();
+
+    assert_eq!(status.daily_usage, Some(4.5));
+    assert!((status.days_remaining.unwrap() - 15.0 / 4.5).abs() < 1e-9);
+    assert!(status.low_stock);
+    assert!(status.projected_run_out.unwrap().to_chrono() > now + Duration::days(3));
+}
+
+#[test]
+fn stock_that_never_runs_out_has_no_run_out_date() {
+    let user_id = ObjectId::new();
+    let mut medication = MedicationTrackerEntry::from(
+        ObjectId::new(),
+        user_id,
+        user_id,
+        String::from("Sinemet"),
+        String::from("25/100mg"),
+        String::from("8am"),
+        Some(crate::app::models::MedicationSchedule {
+            times: vec![chrono::NaiveTime::from_hms_opt(8, 0, 0).unwrap()],
+            ..Default::default()
+        }),
+    );
+    let mut stock = MedicationStock {
+        pack_size: 100,
+        current_count: 30.0,
+        units_per_dose: 0.0,
+        low_stock_days: 7,
+        last_refilled_at: None,
+        next_refill_due: None,
+    };
+    medication.stock = Some(stock.clone());
+    let status = stock_status(&medication, 0, Utc::now()).unwrap();
+    assert_eq!(status.daily_usage, None);
+    assert_eq!(status.projected_run_out, None);
+
+    stock.units_per_dose = 1.0;
+    stock.current_count = 1e300;
+    medication.stock = Some(stock);
+    let status = stock_status(&medication, 0, Utc::now()).unwrap();
+    assert_eq!(status.projected_run_out, None);
+    assert!(!status.low_stock);
+}
@@ -4,12 +4,20 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
-use mongodb::{bson::doc, Database};
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson, DateTime},
+    Database,
+};
+use serde_json::json;
 
 use crate::app::{
     auth::middleware::Auth,
+    caregiver::access::check_access,
+    events::{publish_to_patient_circle, LiveEvent},
+    medication::interactions::warnings_for_medication,
     models::{
-        dto::medication::{AddMedicationPayload, UpdatePath},
+        dto::medication::{AddMedicationPayload, PatientMedicationPath, UpdatePath},
+        CaregiverScope, MedicationDetails, MedicationDoseChanged, MedicationEvent,
         MedicationTrackerEntry,
     },
 };
@@ -31,16 +39,114 @@ pub async fn update_medication(
             .into_response();
     };
 
-    let result = db.collection::<MedicationTrackerEntry>("medications")
-		.update_one(doc! {"_id": path.medication_id, "user_id": auth.id },
-					doc! { "$set": { "medication_name": payload.medication_name, "dose": payload.dose, "timing": payload.timing }} )
-					.await;
+    update_users_medication(&db, auth.id, auth.id, path.medication_id, payload).await
+}
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn update_patient_medication(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<PatientMedicationPath>,
+    Json(payload): Json<AddMedicationPayload>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to update a medication"),
+        )
+            .into_response();
+    };
+
+    if let Err(response) = check_access(
+        &db,
+        auth.id,
+        path.patient_id,
+        CaregiverScope::ManageMedications,
+    )
+    .await
+    {
+        return response;
+    }
+
+    update_users_medication(&db, path.patient_id, auth.id, path.medication_id, payload).await
+}
+
+async fn update_users_medication(
+    db: &Database,
+    user_id: ObjectId,
+    changed_by: ObjectId,
+    medication_id: ObjectId,
+    payload: AddMedicationPayload,
+) -> Response {
+    let collection = db.collection::<MedicationTrackerEntry>("medications");
+    let former = match collection
+        .find_one(doc! {"_id": medication_id, "user_id": user_id })
+        .await
+    {
+        Ok(Some(medication)) => medication,
+        Ok(None) => return StatusCode::BAD_REQUEST.into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let new_details = MedicationDetails {
+        medication_name: payload.medication_name.clone(),
+        dose: payload.dose.clone(),
+        timing: payload.timing.clone(),
+        schedule: payload.schedule.clone(),
+    };
+    if new_details == former.details() {
+        let warnings = warnings_for_medication(db, user_id, medication_id).await;
+        return (StatusCode::OK, Json(json!({ "warnings": warnings }))).into_response();
+    }
+
+    let event = MedicationEvent::DoseChanged(MedicationDoseChanged {
+        former_details: former.details(),
+        new_details,
+        changed_by,
+        changed_at: DateTime::now(),
+    });
+    let (Ok(schedule), Ok(event)) = (to_bson(&payload.schedule), to_bson(&event)) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            String::from("Could not serialize schedule"),
+        )
+            .into_response();
+    };
+
+    let result = collection
+        .update_one(
+            doc! {"_id": medication_id, "user_id": user_id },
+            doc! {
+                "$set": {
+                    "medication_name": payload.medication_name,
+                    "dose": payload.dose,
+                    "timing": payload.timing,
+                    "schedule": schedule
+                },
+                "$push": { "events": event }
+            },
+        )
+        .await;
     match result {
         Ok(result) => {
             if result.modified_count == 0 {
                 return StatusCode::BAD_REQUEST.into_response();
             }
-            StatusCode::OK.into_response()
+            publish_to_patient_circle(
+                db,
+                user_id,
+                LiveEvent::MedicationChanged {
+                    patient_id: user_id,
+                    medication_id,
+                },
+            )
+            .await;
+            let warnings = warnings_for_medication(db, user_id, medication_id).await;
+            (StatusCode::OK, Json(json!({ "warnings": warnings }))).into_response()
         }
         Err(e) => {
             tracing::error!(error = %e, "Error occurred while querying database");
@@ -1,16 +1,28 @@
+pub mod admin;
 pub mod auth;
+pub mod calendar;
 pub mod caregiver;
+pub mod clinician;
+pub mod email;
+pub mod events;
 pub mod form;
 pub mod medication;
 pub mod models;
+pub mod notification;
+pub mod organisation;
+pub mod webhook;
 
 use axum::extract::{Path, State};
 use axum::http::Method;
 use axum_extra::headers::Origin;
-use dotenvy::dotenv;
-use models::{CaregiverToken, User};
+use events::Envelope;
+use models::{
+    AccessLogEntry, CaregiverRequest, CaregiverScope, CaregiverToken, DoseLog, Job, Notification,
+    OrganisationInvitation, PasswordResetToken, Role, Session, User, WebhookDelivery,
+};
 use mongodb::options::IndexOptions;
 use mongodb::IndexModel;
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Context;
@@ -22,7 +34,10 @@ use axum::{
     routing::get,
     Json, Router,
 };
-use mongodb::{bson::doc, Client, Database};
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson},
+    Client, Database,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tokio::net::TcpListener;
@@ -33,10 +48,14 @@ use tower_http::trace::TraceLayer;
 
 use crate::config;
 
+/// How long live events are kept for clients to catch up on after reconnecting
+const EVENT_RETENTION_SECONDS: u64 = 7 * 24 * 60 * 60;
+
 #[allow(clippy::module_name_repetitions)]
 #[derive(Clone, Debug)]
 pub struct AppState {
     pub db: Database,
+    pub email: Arc<dyn email::EmailSender>,
 }
 
 impl AppState {
@@ -54,6 +73,9 @@ impl AppState {
                 |e| tracing::error!(error = %e, "Failed to connect to database at {database_url}"),
             )
             .with_context(|| format!("Failed to connect to database at {database_url}"))?;
+        auth::keys::init()
+            .inspect_err(|e| tracing::error!(error = %e, "Failed to load JWT keys"))
+            .with_context(|| String::from("Failed to load JWT keys"))?;
         create_unique_email_address_index(&db)
             .await
             .inspect_err(
@@ -66,8 +88,79 @@ impl AppState {
                 |e| tracing::error!(error = %e, "Failed to create unique index on caregiver token"),
             )
             .with_context(|| String::from("Failed to create unique index on caregiver token"))?;
+        create_caregiver_request_index(&db)
+            .await
+            .inspect_err(
+                |e| tracing::error!(error = %e, "Failed to create index on caregiver requests"),
+            )
+            .with_context(|| String::from("Failed to create index on caregiver requests"))?;
+        create_access_log_index(&db)
+            .await
+            .inspect_err(|e| tracing::error!(error = %e, "Failed to create index on access log"))
+            .with_context(|| String::from("Failed to create index on access log"))?;
+        create_organisation_member_index(&db)
+            .await
+            .inspect_err(
+                |e| tracing::error!(error = %e, "Failed to create index on organisation members"),
+            )
+            .with_context(|| String::from("Failed to create index on organisation members"))?;
+        create_organisation_invitation_index(&db)
+            .await
+            .inspect_err(
+                |e| tracing::error!(error = %e, "Failed to create index on organisation invitations"),
+            )
+            .with_context(|| String::from("Failed to create index on organisation invitations"))?;
+        create_unique_calendar_token_index(&db)
+            .await
+            .inspect_err(
+                |e| tracing::error!(error = %e, "Failed to create unique index on calendar token"),
+            )
+            .with_context(|| String::from("Failed to create unique index on calendar token"))?;
+        create_session_indexes(&db)
+            .await
+            .inspect_err(|e| tracing::error!(error = %e, "Failed to create indexes on sessions"))
+            .with_context(|| String::from("Failed to create indexes on sessions"))?;
+        create_password_reset_token_indexes(&db)
+            .await
+            .inspect_err(
+                |e| tracing::error!(error = %e, "Failed to create indexes on password reset tokens"),
+            )
+            .with_context(|| String::from("Failed to create indexes on password reset tokens"))?;
+        create_dose_log_index(&db)
+            .await
+            .inspect_err(|e| tracing::error!(error = %e, "Failed to create index on dose logs"))
+            .with_context(|| String::from("Failed to create index on dose logs"))?;
+        create_job_indexes(&db)
+            .await
+            .inspect_err(|e| tracing::error!(error = %e, "Failed to create indexes on jobs"))
+            .with_context(|| String::from("Failed to create indexes on jobs"))?;
+        create_notification_index(&db)
+            .await
+            .inspect_err(|e| tracing::error!(error = %e, "Failed to create index on notifications"))
+            .with_context(|| String::from("Failed to create index on notifications"))?;
+        create_event_indexes(&db)
+            .await
+            .inspect_err(|e| tracing::error!(error = %e, "Failed to create indexes on events"))
+            .with_context(|| String::from("Failed to create indexes on events"))?;
+        create_webhook_delivery_index(&db)
+            .await
+            .inspect_err(
+                |e| tracing::error!(error = %e, "Failed to create index on webhook deliveries"),
+            )
+            .with_context(|| String::from("Failed to create index on webhook deliveries"))?;
+        migrate_caregiver_grants(&db)
+            .await
+            .inspect_err(|e| tracing::error!(error = %e, "Failed to migrate caregiver grants"))
+            .with_context(|| String::from("Failed to migrate caregiver grants"))?;
+        bootstrap_admin(&db)
+            .await
+            .inspect_err(|e| tracing::error!(error = %e, "Failed to set up admin account"))
+            .with_context(|| String::from("Failed to set up admin account"))?;
+        let email = email::from_config()
+            .inspect_err(|e| tracing::error!(error = %e, "Failed to set up email"))
+            .with_context(|| String::from("Failed to set up email"))?;
         tracing::info!("Connected to database at {database_url}");
-        Ok(AppState { db })
+        Ok(AppState { db, email })
     }
 }
 
@@ -77,6 +170,12 @@ impl FromRef<AppState> for Database {
     }
 }
 
+impl FromRef<AppState> for Arc<dyn email::EmailSender> {
+    fn from_ref(app_state: &AppState) -> Arc<dyn email::EmailSender> {
+        app_state.email.clone()
+    }
+}
+
 /// Runs app
 ///
 /// This is where all the routes are defined and gets bound to ports
@@ -84,22 +183,24 @@ impl FromRef<AppState> for Database {
 /// # Panics
 /// This panics upon failed to bind to port or if axum fails to serve app.
 ///
-pub async fn run() {
-    dotenv().ok();
-
-    let app_state = AppState::new().await.unwrap_or_else(|e| {
-        tracing::error!(error = %e, "Error occurred while creating app state");
-        panic!("Error occurred while creating app state");
-    });
-
-    tracing::info!("App state initialized");
-
+pub async fn run(app_state: AppState) {
     let app = Router::new()
         .route("/", get(hello_world))
+        .route("/.well-known/jwks.json", get(auth::keys::jwks))
         .nest("/form", form::router())
         .nest("/auth", auth::router())
         .nest("/caregiver", caregiver::router())
+        .nest("/clinician", clinician::router())
+        .nest("/admin", admin::router())
+        .nest("/organisation", organisation::router())
         .nest("/medication", medication::router())
+        .nest("/calendar", calendar::router())
+        .nest("/notification", notification::router())
+        .nest("/events", events::router())
+        .nest("/webhook", webhook::router())
+        .route_layer(axum::middleware::from_fn(
+            caregiver::access_log::track_endpoint,
+        ))
         .with_state(app_state)
         .layer(CookieManagerLayer::new())
         .layer(
@@ -233,3 +334,227 @@ async fn create_unique_caregiver_token_index(db: &Database) -> anyhow::Result<()
     collection.create_index(index_model).await?;
     Ok(())
 }
+
+async fn create_caregiver_request_index(db: &Database) -> anyhow::Result<()> {
+    let collection = db.collection::<CaregiverRequest>("caregiver_requests");
+    let index_model = IndexModel::builder()
+        .keys(doc! { "patient_id": 1, "status": 1 })
+        .build();
+    collection.create_index(index_model).await?;
+    Ok(())
+}
+
+async fn create_access_log_index(db: &Database) -> anyhow::Result<()> {
+    let collection = db.collection::<AccessLogEntry>("caregiver_access_log");
+    let index_model = IndexModel::builder()
+        .keys(doc! { "patient_id": 1, "accessed_at": -1 })
+        .build();
+    collection.create_index(index_model).await?;
+    Ok(())
+}
+
+async fn create_organisation_member_index(db: &Database) -> anyhow::Result<()> {
+    let collection = db.collection::<User>("users");
+    let index_model = IndexModel::builder()
+        .keys(doc! { "organisation_id": 1 })
+        .options(IndexOptions::builder().sparse(true).build())
+        .build();
+    collection.create_index(index_model).await?;
+    Ok(())
+}
+
+async fn create_organisation_invitation_index(db: &Database) -> anyhow::Result<()> {
+    let collection = db.collection::<OrganisationInvitation>("organisation_invitations");
+    let index_model = IndexModel::builder()
+        .keys(doc! { "user_id": 1, "created_at": -1 })
+        .build();
+    collection.create_index(index_model).await?;
+    Ok(())
+}
+
+async fn create_unique_calendar_token_index(db: &Database) -> anyhow::Result<()> {
+    let collection = db.collection::<User>("users");
+    // Users used to be stored with a null token, which a sparse index still counts
+    collection
+        .update_many(
+            doc! { "calendar_token": null },
+            doc! { "$unset": { "calendar_token": "" } },
+        )
+        .await?;
+    let index_model = IndexModel::builder()
+        .keys(doc! { "calendar_token": 1 })
+        .options(IndexOptions::builder().unique(true).sparse(true).build())
+        .build();
+    collection.create_index(index_model).await?;
+    Ok(())
+}
+
+async fn create_session_indexes(db: &Database) -> anyhow::Result<()> {
+    let collection = db.collection::<Session>("sessions");
+    let refresh_token = IndexModel::builder()
+        .keys(doc! { "refresh_token_hash": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    let previous_refresh_token = IndexModel::builder()
+        .keys(doc! { "previous_refresh_token_hash": 1 })
+        .options(IndexOptions::builder().sparse(true).build())
+        .build();
+    let user = IndexModel::builder()
+        .keys(doc! { "user_id": 1, "last_used_at": -1 })
+        .build();
+    // Expired sessions are no use to anyone
+    let expiry = IndexModel::builder()
+        .keys(doc! { "expires_at": 1 })
+        .options(
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(0))
+                .build(),
+        )
+        .build();
+    collection
+        .create_indexes([refresh_token, previous_refresh_token, user, expiry])
+        .await?;
+    Ok(())
+}
+
+async fn create_password_reset_token_indexes(db: &Database) -> anyhow::Result<()> {
+    let collection = db.collection::<PasswordResetToken>("password_reset_tokens");
+    let token = IndexModel::builder()
+        .keys(doc! { "token_hash": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    let user = IndexModel::builder().keys(doc! { "user_id": 1 }).build();
+    let expiry = IndexModel::builder()
+        .keys(doc! { "expires_at": 1 })
+        .options(
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(0))
+                .build(),
+        )
+        .build();
+    collection.create_indexes([token, user, expiry]).await?;
+    Ok(())
+}
+
+async fn create_dose_log_index(db: &Database) -> anyhow::Result<()> {
+    let collection = db.collection::<DoseLog>("dose_logs");
+    let index_model = IndexModel::builder()
+        .keys(doc! { "user_id": 1, "taken_at": 1 })
+        .build();
+    collection.create_index(index_model).await?;
+    Ok(())
+}
+
+async fn create_job_indexes(db: &Database) -> anyhow::Result<()> {
+    let collection = db.collection::<Job>("jobs");
+    let unique_key = IndexModel::builder()
+        .keys(doc! { "key": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    let due = IndexModel::builder()
+        .keys(doc! { "status": 1, "run_at": 1 })
+        .build();
+    collection.create_indexes([unique_key, due]).await?;
+    Ok(())
+}
+
+async fn create_notification_index(db: &Database) -> anyhow::Result<()> {
+    let collection = db.collection::<Notification>("notifications");
+    let index_model = IndexModel::builder()
+        .keys(doc! { "user_id": 1, "created_at": -1 })
+        .build();
+    collection.create_index(index_model).await?;
+    Ok(())
+}
+
+async fn create_event_indexes(db: &Database) -> anyhow::Result<()> {
+    let collection = db.collection::<Envelope>("events");
+    let audience = IndexModel::builder()
+        .keys(doc! { "audience": 1, "_id": 1 })
+        .build();
+    // Events only need to be kept long enough for clients to reconnect
+    let expiry = IndexModel::builder()
+        .keys(doc! { "created_at": 1 })
+        .options(
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(EVENT_RETENTION_SECONDS))
+                .build(),
+        )
+        .build();
+    collection.create_indexes([audience, expiry]).await?;
+    Ok(())
+}
+
+async fn create_webhook_delivery_index(db: &Database) -> anyhow::Result<()> {
+    let collection = db.collection::<WebhookDelivery>("webhook_deliveries");
+    let index_model = IndexModel::builder()
+        .keys(doc! { "webhook_id": 1, "created_at": -1 })
+        .build();
+    collection.create_index(index_model).await?;
+    Ok(())
+}
+
+/// Turns caregivers stored as bare IDs into grants with the scopes they had before scopes were
+/// added, including medication management for anyone who had been made a medication editor
+async fn migrate_caregiver_grants(db: &Database) -> anyhow::Result<()> {
+    let default_scopes = to_bson(&CaregiverScope::DEFAULT)?;
+    let mut editor_scopes = CaregiverScope::DEFAULT.to_vec();
+    editor_scopes.push(CaregiverScope::ManageMedications);
+    let editor_scopes = to_bson(&editor_scopes)?;
+
+    let result = db
+        .collection::<User>("users")
+        .update_many(
+            doc! { "$or": [
+                { "caregivers": { "$elemMatch": { "$type": "objectId" } } },
+                { "medication_editors": { "$exists": true } }
+            ] },
+            vec![
+                doc! { "$set": { "caregivers": { "$map": {
+                    "input": "$caregivers",
+                    "as": "caregiver",
+                    "in": { "$cond": [
+                        { "$eq": [{ "$type": "$$caregiver" }, "objectId"] },
+                        {
+                            "caregiver_id": "$$caregiver",
+                            "scopes": { "$cond": [
+                                { "$in": ["$$caregiver", { "$ifNull": ["$medication_editors", []] }] },
+                                editor_scopes,
+                                default_scopes
+                            ] },
+                            "granted_at": "$$NOW"
+                        },
+                        "$$caregiver"
+                    ] }
+                } } } },
+                doc! { "$unset": "medication_editors" },
+            ],
+        )
+        .await?;
+    if result.modified_count > 0 {
+        tracing::info!("Migrated caregivers of {} users", result.modified_count);
+    }
+    Ok(())
+}
+
+/// Makes the account with `ADMIN_USER_ID` an admin, so that there is someone who can assign roles
+/// to everyone else
+async fn bootstrap_admin(db: &Database) -> anyhow::Result<()> {
+    let Some(user_id) = config::get_admin_user_id() else {
+        return Ok(());
+    };
+    let user_id = ObjectId::parse_str(&user_id).context("ADMIN_USER_ID is not a valid ID")?;
+    let result = db
+        .collection::<User>("users")
+        .update_one(
+            doc! { "_id": user_id },
+            doc! { "$set": { "role": to_bson(&Role::Admin)? } },
+        )
+        .await?;
+    if result.matched_count == 0 {
+        tracing::warn!("No account found for admin user ID {user_id}");
+    } else if result.modified_count > 0 {
+        tracing::info!("Made {user_id} an admin");
+    }
+    Ok(())
+}
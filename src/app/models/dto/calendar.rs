@@ -0,0 +1,7 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FeedPath {
+    /// The secret followed by `.ics`, as calendar apps expect the extension
+    pub feed: String,
+}
@@ -1,6 +1,9 @@
 use bson::oid::ObjectId;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::app::models::CaregiverScope;
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CaregiverTokenPath {
     pub token: String,
@@ -10,3 +13,82 @@ pub struct CaregiverTokenPath {
 pub struct RemoveCaregiverPath {
     pub caregiver_id: ObjectId,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CaredForPatientPath {
+    pub patient_id: ObjectId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CaregiverScopesPath {
+    pub caregiver_id: ObjectId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CaregiverScopesPayload {
+    pub scopes: Vec<CaregiverScope>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GenerateTokenPayload {
+    /// What the caregiver will be allowed to do once they use the token
+    pub scopes: Vec<CaregiverScope>,
+    /// When the caregiver's access should end. Leave out to give access until it is removed
+    pub access_expires_at: Option<DateTime<Utc>>,
+    /// How long the token can be used for. Defaults to 3 days
+    pub valid_for_hours: Option<u32>,
+    /// Let whoever uses the token become a caregiver without approval
+    #[serde(default)]
+    pub trusted: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CaregiverRequestPath {
+    pub request_id: ObjectId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct InvitationPath {
+    pub invitation_id: ObjectId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct FindInvitationsQuery {
+    /// Also list invitations that have already been used, along with who used them
+    pub include_redeemed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CaregiverExpiryPath {
+    pub caregiver_id: ObjectId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CaregiverExpiryPayload {
+    /// Leave out to give access until it is removed
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum QrFormat {
+    #[default]
+    Png,
+    Svg,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct QrQuery {
+    pub format: QrFormat,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct FindAccessLogQuery {
+    /// Only return accesses by this caregiver
+    pub caregiver_id: Option<ObjectId>,
+    /// Only return accesses before this time, to load older pages
+    pub before: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+}
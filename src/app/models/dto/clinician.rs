@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CaseloadQuery {
+    /// Starts at 1
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
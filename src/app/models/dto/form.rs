@@ -1,7 +1,7 @@
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 
-use crate::app::models::{Question, QuestionAndAnswer};
+use crate::app::models::{FormSchedule, Question, QuestionAndAnswer};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CreateFormPayload {
@@ -9,6 +9,7 @@ pub struct CreateFormPayload {
     pub title: String,
     pub description: Option<String>,
     pub questions: Vec<Question>,
+    pub schedule: Option<FormSchedule>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -25,3 +26,26 @@ pub struct SubmitPath {
 pub struct SubmitPayload {
     pub answers: Vec<QuestionAndAnswer>,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SchedulePath {
+    pub form_id: ObjectId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SchedulePayload {
+    /// Leave out to stop the form being scheduled
+    pub schedule: Option<FormSchedule>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EditQuestionPath {
+    pub form_id: ObjectId,
+    pub question_id: ObjectId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EditQuestionPayload {
+    /// Multichoice options should keep their IDs so earlier answers still refer to them
+    pub question: Question,
+}
@@ -1,11 +1,15 @@
 use bson::oid::ObjectId;
+use chrono::{NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::app::models::{DoseStatus, MedicationSchedule};
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct AddMedicationPayload {
     pub medication_name: String,
     pub dose: String,
     pub timing: String,
+    pub schedule: Option<MedicationSchedule>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -13,6 +17,7 @@ pub struct UpdateMedicationPayload {
     pub medication_name: String,
     pub dose: String,
     pub timing: String,
+    pub schedule: Option<MedicationSchedule>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -29,3 +34,94 @@ pub struct UpdatePath {
 pub struct RemovePath {
     pub medication_id: ObjectId,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LogPath {
+    pub medication_id: ObjectId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LogDosePayload {
+    pub status: DoseStatus,
+    /// Defaults to the time the dose is logged
+    pub taken_at: Option<chrono::DateTime<Utc>>,
+    pub note: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DoseLogQuery {
+    /// The (UTC) day to list dose logs for
+    pub date: NaiveDate,
+    pub medication_id: Option<ObjectId>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AdherenceQuery {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Defaults to the signed in user
+    pub patient_id: Option<ObjectId>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PatientQuery {
+    /// Defaults to the signed in user
+    pub patient_id: Option<ObjectId>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PatientPath {
+    pub patient_id: ObjectId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PatientMedicationPath {
+    pub patient_id: ObjectId,
+    pub medication_id: ObjectId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FindAllQuery {
+    #[serde(default)]
+    pub include_stopped: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RestartPath {
+    pub medication_id: ObjectId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HistoryQuery {
+    /// Defaults to the signed in user
+    pub patient_id: Option<ObjectId>,
+    /// Only show the history of this medication
+    pub medication_id: Option<ObjectId>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StockPath {
+    pub medication_id: ObjectId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SetStockPayload {
+    pub pack_size: u32,
+    pub current_count: f64,
+    /// Defaults to one unit per dose
+    pub units_per_dose: Option<f64>,
+    /// Defaults to warning a week before running out
+    pub low_stock_days: Option<u32>,
+    pub next_refill_due: Option<chrono::DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RefillPayload {
+    /// Number of whole packs collected
+    #[serde(default)]
+    pub packs: u32,
+    /// Number of loose units collected, on top of any packs
+    #[serde(default)]
+    pub units: f64,
+    pub next_refill_due: Option<chrono::DateTime<Utc>>,
+}
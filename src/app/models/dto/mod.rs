@@ -1,4 +1,9 @@
+pub mod calendar;
 pub mod caregiver;
+pub mod clinician;
 pub mod form;
 pub mod medication;
+pub mod notification;
+pub mod organisation;
 pub mod user;
+pub mod webhook;
@@ -0,0 +1,17 @@
+use bson::oid::ObjectId;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NotificationPath {
+    pub notification_id: ObjectId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FindNotificationsQuery {
+    #[serde(default)]
+    pub unread_only: bool,
+    /// Only return notifications created before this time, to load older pages
+    pub before: Option<chrono::DateTime<Utc>>,
+    pub limit: Option<i64>,
+}
@@ -0,0 +1,46 @@
+use bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+use crate::app::models::{CaregiverScope, OrganisationRole};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CreateOrganisationPayload {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OrganisationPath {
+    pub organisation_id: ObjectId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OrganisationMemberPath {
+    pub organisation_id: ObjectId,
+    pub user_id: ObjectId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EnrolPayload {
+    pub email_address: String,
+    pub role: OrganisationRole,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OrganisationInvitationPath {
+    pub invitation_id: ObjectId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AssignClinicianPayload {
+    pub patient_id: ObjectId,
+    pub clinician_id: ObjectId,
+    /// What the clinician will be allowed to do. Defaults to what caregivers are given.
+    pub scopes: Option<Vec<CaregiverScope>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AssignmentPath {
+    pub organisation_id: ObjectId,
+    pub patient_id: ObjectId,
+    pub clinician_id: ObjectId,
+}
@@ -1,5 +1,8 @@
+use bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 
+use crate::app::models::Role;
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CreateUserPayload {
     pub first_name: String,
@@ -9,3 +12,29 @@ pub struct CreateUserPayload {
     pub password: String,
     pub is_patient: bool,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RolePath {
+    pub user_id: ObjectId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SetRolePayload {
+    pub role: Role,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SessionPath {
+    pub session_id: ObjectId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ForgotPasswordPayload {
+    pub email_address: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ResetPasswordPayload {
+    pub token: String,
+    pub password: String,
+}
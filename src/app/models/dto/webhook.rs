@@ -0,0 +1,37 @@
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::app::models::{WebhookEventType, WebhookSubscription};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CreateWebhookPayload {
+    pub url: String,
+    pub event_types: Vec<WebhookEventType>,
+    /// Generated if left out
+    pub secret: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebhookPath {
+    pub webhook_id: ObjectId,
+}
+
+/// A webhook subscription without its secret
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebhookResponse {
+    pub id: ObjectId,
+    pub url: String,
+    pub event_types: Vec<WebhookEventType>,
+    pub created_at: DateTime,
+}
+
+impl From<WebhookSubscription> for WebhookResponse {
+    fn from(subscription: WebhookSubscription) -> Self {
+        Self {
+            id: subscription.id,
+            url: subscription.url,
+            event_types: subscription.event_types,
+            created_at: subscription.created_at,
+        }
+    }
+}
@@ -1,7 +1,7 @@
 pub mod dto;
 
-use chrono::{Duration, Utc};
-use mongodb::bson::{oid::ObjectId, DateTime};
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime, Utc, Weekday};
+use mongodb::bson::{doc, oid::ObjectId, DateTime, Document};
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
 use serde::{Deserialize, Serialize};
@@ -16,7 +16,17 @@ pub struct User {
     pub email_address: String,
     pub hashed_password: String,
     pub is_patient: bool,
-    pub caregivers: Vec<ObjectId>,
+    /// Users created before roles were added don't have one, see [`User::role`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<Role>,
+    /// The organisation (tenant) the user was enrolled in by a clinic, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organisation_id: Option<ObjectId>,
+    /// Caregivers of this user, along with what each may see and do
+    pub caregivers: Vec<CaregiverGrant>,
+    /// Secret used in the URL of the user's calendar feed, if they have one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calendar_token: Option<String>,
 }
 
 impl User {
@@ -35,9 +45,225 @@ impl User {
             email_address,
             hashed_password: password,
             is_patient,
+            role: Some(Role::from_is_patient(is_patient)),
+            organisation_id: None,
             caregivers: vec![],
+            calendar_token: None,
         }
     }
+
+    /// The user's role, falling back to whether they signed up as a patient if they don't have one
+    #[must_use]
+    pub fn role(&self) -> Role {
+        self.role
+            .unwrap_or_else(|| Role::from_is_patient(self.is_patient))
+    }
+
+    /// Finds the grant given to a caregiver, if they are one of this user's caregivers and their
+    /// access hasn't expired
+    #[must_use]
+    pub fn caregiver_grant(&self, caregiver_id: ObjectId) -> Option<&CaregiverGrant> {
+        let now = DateTime::now();
+        self.caregivers
+            .iter()
+            .find(|grant| grant.caregiver_id == caregiver_id && grant.is_active(now))
+    }
+
+    /// Whether someone in `organisation_id` may be given access to this user's data
+    ///
+    /// Members of an organisation can only reach patients in the same organisation. Caregivers who
+    /// don't belong to one, such as family members, are only limited by what the patient grants
+    /// them.
+    #[must_use]
+    pub fn is_in_tenant_of(&self, organisation_id: Option<ObjectId>) -> bool {
+        organisation_id.is_none_or(|organisation_id| self.organisation_id == Some(organisation_id))
+    }
+
+    /// Filter for the users that someone in `organisation_id` may be given access to, matching
+    /// [`User::is_in_tenant_of`]
+    #[must_use]
+    pub fn tenant_filter(organisation_id: Option<ObjectId>) -> Document {
+        match organisation_id {
+            Some(organisation_id) => doc! { "organisation_id": organisation_id },
+            None => doc! {},
+        }
+    }
+}
+
+/// A clinic whose clinicians look after the patients enrolled in it. Each one is kept apart from
+/// the others.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Organisation {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub name: String,
+    /// Members who can enrol patients and assign clinicians to them
+    pub admins: Vec<ObjectId>,
+    pub created_at: DateTime,
+}
+
+/// What someone is enrolled in an organisation as
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrganisationRole {
+    Patient,
+    Clinician,
+    /// Can enrol members and assign clinicians. Only site admins can enrol these.
+    Admin,
+}
+
+/// An organisation asking a user to join it. They are only enrolled once they accept.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OrganisationInvitation {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub organisation_id: ObjectId,
+    /// The organisation's name, so the user can tell who is asking
+    pub organisation_name: String,
+    pub user_id: ObjectId,
+    pub role: OrganisationRole,
+    pub invited_by: ObjectId,
+    pub created_at: DateTime,
+}
+
+/// What kind of user someone is
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Patient,
+    /// Family member or friend who helps look after patients
+    Caregiver,
+    /// Health professional with a caseload of patients
+    Clinician,
+    /// Can change other users' roles
+    Admin,
+}
+
+impl Role {
+    #[must_use]
+    pub fn from_is_patient(is_patient: bool) -> Role {
+        if is_patient {
+            Role::Patient
+        } else {
+            Role::Caregiver
+        }
+    }
+}
+
+/// Something a patient can allow a caregiver to do
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaregiverScope {
+    /// See forms and the answers to slider and multichoice questions
+    ViewSymptoms,
+    /// See answers to free-form questions, which may be more personal
+    ViewFreeText,
+    ViewMedications,
+    /// Add, update, stop and restart medications and track their stock
+    ManageMedications,
+    /// Fill in forms for the patient
+    SubmitOnBehalf,
+    /// Change the questions and schedules of forms
+    EditForms,
+}
+
+impl CaregiverScope {
+    /// Scopes given when a patient doesn't choose any. These are what every caregiver could do
+    /// before scopes were added.
+    pub const DEFAULT: [CaregiverScope; 4] = [
+        CaregiverScope::ViewSymptoms,
+        CaregiverScope::ViewFreeText,
+        CaregiverScope::ViewMedications,
+        CaregiverScope::SubmitOnBehalf,
+    ];
+}
+
+impl CaregiverScope {
+    /// Whether using the scope only reads the patient's data, or changes it
+    #[must_use]
+    pub fn action(self) -> AccessAction {
+        match self {
+            CaregiverScope::ViewSymptoms
+            | CaregiverScope::ViewFreeText
+            | CaregiverScope::ViewMedications => AccessAction::Read,
+            CaregiverScope::ManageMedications
+            | CaregiverScope::SubmitOnBehalf
+            | CaregiverScope::EditForms => AccessAction::Write,
+        }
+    }
+
+    /// The kind of data the scope gives access to
+    #[must_use]
+    pub fn resource(self) -> AccessResource {
+        match self {
+            CaregiverScope::ViewSymptoms
+            | CaregiverScope::ViewFreeText
+            | CaregiverScope::SubmitOnBehalf
+            | CaregiverScope::EditForms => AccessResource::Forms,
+            CaregiverScope::ViewMedications | CaregiverScope::ManageMedications => {
+                AccessResource::Medications
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessAction {
+    Read,
+    Write,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessResource {
+    Forms,
+    Medications,
+}
+
+/// A record of a caregiver reading or changing a patient's data. These are only ever added, never
+/// changed or removed.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AccessLogEntry {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub patient_id: ObjectId,
+    pub caregiver_id: ObjectId,
+    /// The scope that allowed the access
+    pub scope: CaregiverScope,
+    pub action: AccessAction,
+    pub resource: AccessResource,
+    /// Method and route of the request, such as `GET /medication/patient/:patient_id/find`
+    pub endpoint: Option<String>,
+    pub accessed_at: DateTime,
+}
+
+fn default_caregiver_scopes() -> Vec<CaregiverScope> {
+    CaregiverScope::DEFAULT.to_vec()
+}
+
+/// Someone a patient has made their caregiver, and what they have allowed them to do
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CaregiverGrant {
+    pub caregiver_id: ObjectId,
+    pub scopes: Vec<CaregiverScope>,
+    pub granted_at: DateTime,
+    /// When the caregiver loses access, if they were only given it for a while
+    #[serde(default)]
+    pub expires_at: Option<DateTime>,
+}
+
+impl CaregiverGrant {
+    #[must_use]
+    pub fn has_scope(&self, scope: CaregiverScope) -> bool {
+        self.scopes.contains(&scope)
+    }
+
+    #[must_use]
+    pub fn is_active(&self, now: DateTime) -> bool {
+        self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+
+    /// Matches grants inside `$elemMatch` that haven't expired
+    #[must_use]
+    pub fn active_filter(now: DateTime) -> Document {
+        doc! { "$or": [{ "expires_at": null }, { "expires_at": { "$gt": now } }] }
+    }
 }
 
 /// A form that clients fill in is represented here
@@ -106,6 +332,7 @@ impl User {
 ///             submitted_by: ObjectId::new(),
 ///         }),
 ///     ],
+///     schedule: None,
 /// };
 /// ```
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -123,9 +350,85 @@ pub struct Form {
     pub questions: Vec<Question>,
     /// List of events such as a user filling in a form or a moderator updating the form
     pub events: Vec<Event>,
+    /// How often the form should be filled in, if it is meant to be filled in regularly
+    pub schedule: Option<FormSchedule>,
+}
+
+/// How often a form should be filled in. Times and dates are in UTC.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FormSchedule {
+    /// Number of days between each time the form is due
+    pub every_days: u32,
+    /// Time of day the form is due
+    pub time: NaiveTime,
+    /// First day the form is due
+    pub starts_on: NaiveDate,
+}
+
+/// How long after a scheduled form is due that it counts as overdue
+pub const OVERDUE_AFTER_HOURS: i64 = 24;
+
+impl FormSchedule {
+    /// Lists every time the form is due between `from` and `to` (inclusive), in order
+    #[must_use]
+    pub fn due_between(
+        &self,
+        from: chrono::DateTime<Utc>,
+        to: chrono::DateTime<Utc>,
+    ) -> Vec<chrono::DateTime<Utc>> {
+        let every_days = i64::from(self.every_days.max(1));
+        let first_due = self.starts_on.and_time(self.time).and_utc();
+        // Skip straight to the last time the form was due before `from`
+        let mut periods = 0;
+        if from > first_due {
+            periods = (from - first_due).num_days() / every_days;
+        }
+        let mut due = Vec::new();
+        while let Some(time) = first_due
+            .checked_add_signed(Duration::days(periods * every_days))
+            .filter(|time| *time <= to)
+        {
+            if time >= from {
+                due.push(time);
+            }
+            periods += 1;
+        }
+        due
+    }
 }
 
 impl Form {
+    /// When the form was last filled in, if it ever has been
+    #[must_use]
+    pub fn last_submitted_at(&self) -> Option<DateTime> {
+        self.events
+            .iter()
+            .filter_map(|event| match event {
+                Event::FormSubmitted(submission) => Some(submission.submitted_at),
+                Event::QuestionEdited(..) => None,
+            })
+            .max()
+    }
+
+    /// The last time the form was due, if it is scheduled and hasn't been filled in since then
+    /// even though `grace` has passed
+    #[must_use]
+    pub fn overdue_since(
+        &self,
+        now: chrono::DateTime<Utc>,
+        grace: Duration,
+    ) -> Option<chrono::DateTime<Utc>> {
+        let schedule = self.schedule.as_ref()?;
+        let period = Duration::days(i64::from(schedule.every_days.max(1)));
+        let last_due = *schedule
+            .due_between(now - period - grace, now - grace)
+            .last()?;
+        let submitted_since = self
+            .last_submitted_at()
+            .is_some_and(|submitted_at| submitted_at.to_chrono() >= last_due);
+        (!submitted_since).then_some(last_due)
+    }
+
     #[must_use]
     pub fn from(
         id: ObjectId,
@@ -134,6 +437,7 @@ impl Form {
         created_by: ObjectId,
         user_id: ObjectId,
         mut questions: Vec<Question>,
+        schedule: Option<FormSchedule>,
     ) -> Self {
         for question in &mut questions {
             match question {
@@ -161,6 +465,18 @@ impl Form {
             created_at: DateTime::now(),
             questions,
             events: Vec::new(),
+            schedule,
+        }
+    }
+
+    /// Removes answers to free-form questions, for viewers who may only see symptoms
+    pub fn redact_free_text(&mut self) {
+        for event in &mut self.events {
+            if let Event::FormSubmitted(submission) = event {
+                submission
+                    .answers
+                    .retain(|answer| !matches!(answer, QuestionAndAnswer::FreeForm(..)));
+            }
         }
     }
 }
@@ -205,6 +521,31 @@ pub enum Question {
     FreeForm(FreeFormQuestion),
 }
 
+impl Question {
+    #[must_use]
+    pub fn id(&self) -> Option<ObjectId> {
+        match self {
+            Question::Multichoice(question) => question.id,
+            Question::Slider(question) => question.id,
+            Question::FreeForm(question) => question.id,
+        }
+    }
+
+    /// Gives the question an ID, along with any multichoice options that don't have one yet
+    pub fn assign_ids(&mut self, id: ObjectId) {
+        match self {
+            Question::Multichoice(question) => {
+                question.id = Some(id);
+                for option in &mut question.options {
+                    option.id.get_or_insert_with(ObjectId::new);
+                }
+            }
+            Question::Slider(question) => question.id = Some(id),
+            Question::FreeForm(question) => question.id = Some(id),
+        }
+    }
+}
+
 /// ID of choice in the questions that is selected
 pub type MultichoiceAnswer = Vec<ObjectId>;
 /// Numerical value that the user selects
@@ -261,14 +602,91 @@ pub struct MultichoiceQuestionOption {
     pub id: Option<ObjectId>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+/// An invitation a patient gives to someone so that they can become their caregiver
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CaregiverToken {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
     pub token: String,
     pub user_id: ObjectId,
-    created_at: mongodb::bson::DateTime,
-    expired_by: mongodb::bson::DateTime,
+    /// What the caregiver will be allowed to do once they use the token
+    #[serde(default = "default_caregiver_scopes")]
+    pub scopes: Vec<CaregiverScope>,
+    /// When the caregiver's access will expire, if the patient only wants to give it for a while
+    #[serde(default)]
+    pub access_expires_at: Option<DateTime>,
+    pub created_at: DateTime,
+    /// After this the token can no longer be used
+    pub expired_by: DateTime,
+    /// Who used the token. Each token can only be used once.
+    #[serde(default)]
+    pub redeemed_by: Option<ObjectId>,
+    #[serde(default)]
+    pub redeemed_at: Option<DateTime>,
+    /// Whoever uses a trusted token becomes a caregiver without the patient having to approve it
+    #[serde(default)]
+    pub trusted: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaregiverRequestStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// Someone used a caregiver token and is waiting for the patient to approve them
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CaregiverRequest {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub patient_id: ObjectId,
+    /// The token that was used
+    pub token_id: Option<ObjectId>,
+    pub caregiver_id: ObjectId,
+    /// Who the caregiver says they are, so the patient can tell whether they know them
+    pub caregiver_first_name: String,
+    pub caregiver_last_name: String,
+    pub caregiver_email_address: String,
+    pub scopes: Vec<CaregiverScope>,
+    pub access_expires_at: Option<DateTime>,
+    pub status: CaregiverRequestStatus,
+    pub requested_at: DateTime,
+    pub decided_at: Option<DateTime>,
+}
+/// A device someone is signed in on. Each one has a refresh token, which is swapped for a new one
+/// every time it is used.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Session {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub user_id: ObjectId,
+    /// SHA-256 of the current refresh token. The token itself is never stored.
+    pub refresh_token_hash: String,
+    /// SHA-256 of the token that the current one replaced, so that reuse of a stolen token can be
+    /// noticed
+    pub previous_refresh_token_hash: Option<String>,
+    /// The browser or app that signed in, from its `User-Agent` header
+    pub user_agent: Option<String>,
+    pub created_at: DateTime,
+    pub last_used_at: DateTime,
+    pub expires_at: DateTime,
+}
+
+/// A link sent by email that lets someone choose a new password. It can only be used once.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PasswordResetToken {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub user_id: ObjectId,
+    /// SHA-256 of the token in the link. The token itself is never stored.
+    pub token_hash: String,
+    pub created_at: DateTime,
+    pub expires_at: DateTime,
+    pub used_at: Option<DateTime>,
 }
-fn generate_random_string(length: usize) -> String {
+
+pub(crate) fn generate_random_string(length: usize) -> String {
     let rng = thread_rng();
     rng.sample_iter(&Alphanumeric)
         .take(length)
@@ -278,13 +696,24 @@ fn generate_random_string(length: usize) -> String {
 }
 
 impl CaregiverToken {
-    pub fn new(user_id: ObjectId) -> CaregiverToken {
-        let expired_by = Utc::now() + Duration::days(3);
+    pub fn new(
+        user_id: ObjectId,
+        scopes: Vec<CaregiverScope>,
+        access_expires_at: Option<DateTime>,
+        valid_for: Duration,
+    ) -> CaregiverToken {
+        let expired_by = Utc::now() + valid_for;
         CaregiverToken {
+            id: Some(ObjectId::new()),
             token: generate_random_string(10),
             expired_by: mongodb::bson::DateTime::from_millis(expired_by.timestamp_millis()),
             user_id,
+            scopes,
+            access_expires_at,
             created_at: mongodb::bson::DateTime::now(),
+            redeemed_by: None,
+            redeemed_at: None,
+            trusted: false,
         }
     }
 }
@@ -293,11 +722,20 @@ impl CaregiverToken {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct MedicationTrackerEntry {
     #[serde(rename = "_id")]
-    id: ObjectId,
+    pub id: ObjectId,
     pub user_id: ObjectId,
-    medication_name: String,
-    dose: String,
-    timing: String,
+    pub medication_name: String,
+    pub dose: String,
+    pub timing: String,
+    /// Structured version of `timing`, used to work out when doses are expected
+    pub schedule: Option<MedicationSchedule>,
+    /// Set while the medication is stopped; stopped medications are kept so their history isn't lost
+    pub stopped_at: Option<DateTime>,
+    /// How much of the medication is left, if the user is tracking it
+    pub stock: Option<MedicationStock>,
+    /// List of changes made to this entry, such as the dose being changed or the medication being stopped
+    #[serde(default)]
+    pub events: Vec<MedicationEvent>,
 }
 
 impl MedicationTrackerEntry {
@@ -305,16 +743,497 @@ impl MedicationTrackerEntry {
     pub fn from(
         id: ObjectId,
         user_id: ObjectId,
+        added_by: ObjectId,
         medication_name: String,
         dose: String,
         timing: String,
+        schedule: Option<MedicationSchedule>,
     ) -> Self {
-        Self {
+        let mut medication = Self {
             id,
             user_id,
             medication_name,
             dose,
             timing,
+            schedule,
+            stopped_at: None,
+            stock: None,
+            events: Vec::new(),
+        };
+        medication
+            .events
+            .push(MedicationEvent::Added(MedicationAdded {
+                details: medication.details(),
+                added_by,
+                added_at: DateTime::now(),
+            }));
+        medication
+    }
+
+    /// Whether the medication was being taken at the given time, going by when it was added, stopped
+    /// and restarted
+    ///
+    /// Entries from before the history was kept have no events, and count as always taken.
+    #[must_use]
+    pub fn was_active_at(&self, at: chrono::DateTime<Utc>) -> bool {
+        let mut active = !self
+            .events
+            .iter()
+            .any(|event| matches!(event, MedicationEvent::Added(..)));
+        for event in &self.events {
+            if event.happened_at().to_chrono() > at {
+                break;
+            }
+            match event {
+                MedicationEvent::Stopped(..) => active = false,
+                MedicationEvent::Added(..) | MedicationEvent::Restarted(..) => active = true,
+                MedicationEvent::DoseChanged(..) => {}
+            }
+        }
+        active
+    }
+
+    #[must_use]
+    pub fn details(&self) -> MedicationDetails {
+        MedicationDetails {
+            medication_name: self.medication_name.clone(),
+            dose: self.dose.clone(),
+            timing: self.timing.clone(),
+            schedule: self.schedule.clone(),
+        }
+    }
+}
+
+/// The name, dose and timing of a medication at some point in time
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MedicationDetails {
+    pub medication_name: String,
+    pub dose: String,
+    pub timing: String,
+    pub schedule: Option<MedicationSchedule>,
+}
+
+/// This represents a change to a medication tracker entry, kept so clinicians can follow titration
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum MedicationEvent {
+    Added(MedicationAdded),
+    DoseChanged(MedicationDoseChanged),
+    Stopped(MedicationStopped),
+    Restarted(MedicationRestarted),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MedicationAdded {
+    pub details: MedicationDetails,
+    pub added_by: ObjectId,
+    pub added_at: DateTime,
+}
+
+/// This represents the name, dose or timing of a medication being updated
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MedicationDoseChanged {
+    pub former_details: MedicationDetails,
+    pub new_details: MedicationDetails,
+    pub changed_by: ObjectId,
+    pub changed_at: DateTime,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MedicationStopped {
+    pub stopped_by: ObjectId,
+    pub stopped_at: DateTime,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MedicationRestarted {
+    pub restarted_by: ObjectId,
+    pub restarted_at: DateTime,
+}
+
+impl MedicationEvent {
+    /// When the change happened
+    #[must_use]
+    pub fn happened_at(&self) -> DateTime {
+        match self {
+            MedicationEvent::Added(event) => event.added_at,
+            MedicationEvent::DoseChanged(event) => event.changed_at,
+            MedicationEvent::Stopped(event) => event.stopped_at,
+            MedicationEvent::Restarted(event) => event.restarted_at,
+        }
+    }
+}
+
+/// Supply of a medication, counted down as doses are logged
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MedicationStock {
+    /// Number of units (tablets, patches, etc) in a pack
+    pub pack_size: u32,
+    /// Number of units left
+    pub current_count: f64,
+    /// Number of units taken in each dose
+    pub units_per_dose: f64,
+    /// Warn when there are this many days of supply left or fewer
+    pub low_stock_days: u32,
+    pub last_refilled_at: Option<DateTime>,
+    pub next_refill_due: Option<DateTime>,
+}
+
+/// When doses of a medication are due. All times and dates are in UTC.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct MedicationSchedule {
+    /// Times of day that a dose is due
+    pub times: Vec<NaiveTime>,
+    /// Days of the week that doses are due on, or every day if empty
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    pub starts_on: Option<NaiveDate>,
+    pub ends_on: Option<NaiveDate>,
+}
+
+impl MedicationSchedule {
+    /// Lists every dose due between `from` and `to` (inclusive), in order
+    #[must_use]
+    pub fn doses_between(
+        &self,
+        from: chrono::DateTime<Utc>,
+        to: chrono::DateTime<Utc>,
+    ) -> Vec<chrono::DateTime<Utc>> {
+        let mut times = self.times.clone();
+        times.sort();
+        let mut doses = Vec::new();
+        let mut date = from.date_naive();
+        while date <= to.date_naive() {
+            let in_range = self.starts_on.is_none_or(|start| date >= start)
+                && self.ends_on.is_none_or(|end| date <= end);
+            if in_range && (self.days.is_empty() || self.days.contains(&date.weekday())) {
+                doses.extend(
+                    times
+                        .iter()
+                        .map(|time| date.and_time(*time).and_utc())
+                        .filter(|dose| *dose >= from && *dose <= to),
+                );
+            }
+            let Some(next) = date.succ_opt() else {
+                break;
+            };
+            date = next;
+        }
+        doses
+    }
+}
+
+/// Whether a dose of a medication was taken on time, taken late, or skipped
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DoseStatus {
+    Taken,
+    Late,
+    Skipped,
+}
+
+/// A single logged dose of a medication tracker entry
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DoseLog {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub medication_id: ObjectId,
+    /// This is the ID of the user the medication belongs to
+    pub user_id: ObjectId,
+    pub status: DoseStatus,
+    /// This is when the dose was actually taken, or when it was due if it was skipped
+    pub taken_at: DateTime,
+    pub note: Option<String>,
+    pub logged_by: ObjectId,
+    pub logged_at: DateTime,
+}
+
+/// What a scheduled job should do when it runs
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum JobKind {
+    /// Remind a user to take a dose of a medication
+    DoseReminder {
+        medication_id: ObjectId,
+        due_at: DateTime,
+    },
+    /// Remind a user to fill in a form
+    FormDue { form_id: ObjectId, due_at: DateTime },
+    /// Tell a user if they still haven't filled in a form some time after it was due
+    FormOverdue { form_id: ObjectId, due_at: DateTime },
+    /// Send an event to a webhook
+    WebhookDelivery { delivery_id: ObjectId },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobStatus {
+    Pending,
+    Done,
+    /// The job was no longer needed by the time it ran, e.g. the medication was stopped
+    Skipped,
+    /// The job failed too many times to be tried again
+    Failed,
+}
+
+/// A piece of background work, stored so that it still runs if the service restarts
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Job {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    /// Identifies what the job is for, so the same job is never scheduled twice
+    pub key: String,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    pub run_at: DateTime,
+    /// A worker has claimed the job until this time. If it is in the past the worker is assumed
+    /// to have crashed and the job can be claimed again.
+    pub locked_until: Option<DateTime>,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub created_at: DateTime,
+    pub finished_at: Option<DateTime>,
+}
+
+impl Job {
+    #[must_use]
+    pub fn from(key: String, kind: JobKind, run_at: DateTime) -> Self {
+        Self {
+            id: ObjectId::new(),
+            key,
+            kind,
+            status: JobStatus::Pending,
+            run_at,
+            locked_until: None,
+            attempts: 0,
+            last_error: None,
+            created_at: DateTime::now(),
+            finished_at: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum NotificationKind {
+    DoseReminder {
+        medication_id: ObjectId,
+    },
+    FormDue {
+        form_id: ObjectId,
+    },
+    FormOverdue {
+        form_id: ObjectId,
+    },
+    /// Someone became the user's caregiver
+    CaregiverAdded {
+        caregiver_id: ObjectId,
+    },
+    /// One of the user's caregivers stopped being their caregiver
+    CaregiverLeft {
+        caregiver_id: ObjectId,
+    },
+    /// A patient removed the user as their caregiver
+    CaregiverRemoved {
+        patient_id: ObjectId,
+    },
+    /// Someone used one of the user's caregiver tokens and is waiting for approval
+    CaregiverRequested {
+        request_id: ObjectId,
+        caregiver_id: ObjectId,
+    },
+    /// A patient approved the user's request to become their caregiver
+    CaregiverRequestApproved {
+        patient_id: ObjectId,
+    },
+    /// A patient rejected the user's request to become their caregiver
+    CaregiverRequestRejected {
+        patient_id: ObjectId,
+    },
+    /// Time-limited access between a patient and a caregiver ran out
+    CaregiverAccessExpired {
+        patient_id: ObjectId,
+        caregiver_id: ObjectId,
+    },
+    /// An organisation invited the user to join it
+    OrganisationInvitation {
+        organisation_id: ObjectId,
+        invitation_id: ObjectId,
+    },
+    /// An organisation admin asked for a clinician to become one of the user's caregivers, which
+    /// the user has to approve
+    ClinicianAssigned {
+        organisation_id: ObjectId,
+        clinician_id: ObjectId,
+        request_id: ObjectId,
+    },
+}
+
+/// A message for a user, delivered through one or more notification channels
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Notification {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    /// This is the ID of the user the notification is for
+    pub user_id: ObjectId,
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+    pub created_at: DateTime,
+    pub read_at: Option<DateTime>,
+    /// Dismissed notifications are kept but no longer shown to the user
+    #[serde(default)]
+    pub dismissed_at: Option<DateTime>,
+}
+
+impl Notification {
+    #[must_use]
+    pub fn from(user_id: ObjectId, kind: NotificationKind, title: String, body: String) -> Self {
+        Self {
+            id: ObjectId::new(),
+            user_id,
+            kind,
+            title,
+            body,
+            created_at: DateTime::now(),
+            read_at: None,
+            dismissed_at: None,
         }
     }
 }
+
+/// Kinds of event that a webhook can be subscribed to
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WebhookEventType {
+    FormSubmitted,
+    MedicationChanged,
+    Notification,
+    /// Sent when testing a webhook, whatever it is subscribed to
+    Ping,
+}
+
+/// A URL that is sent events for the patients its owner can see
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebhookSubscription {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    /// This is the ID of the user that created the webhook. It is sent every event they could see.
+    pub user_id: ObjectId,
+    pub url: String,
+    pub event_types: Vec<WebhookEventType>,
+    /// Used to sign payloads so the receiver can check they came from us
+    pub secret: String,
+    pub created_at: DateTime,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    /// Every attempt to deliver the event failed
+    Failed,
+}
+
+/// A single event sent to a webhook, along with each attempt to send it
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebhookDelivery {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub webhook_id: ObjectId,
+    pub event_type: WebhookEventType,
+    /// The JSON body, kept as is so that retries are signed and sent identically
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: Vec<DeliveryAttempt>,
+    pub created_at: DateTime,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DeliveryAttempt {
+    pub attempted_at: DateTime,
+    /// HTTP status the receiver responded with, if it responded
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+}
+
+#[test]
+fn medications_are_not_taken_before_they_are_added() {
+    let user_id = ObjectId::new();
+    let mut medication = MedicationTrackerEntry::from(
+        ObjectId::new(),
+        user_id,
+        user_id,
+        String::from("Sinemet"),
+        String::from("25/100mg"),
+        String::from("8am"),
+        None,
+    );
+    let now = Utc::now();
+
+    assert!(!medication.was_active_at(now - Duration::days(1)));
+    assert!(medication.was_active_at(now + Duration::minutes(1)));
+
+    medication.events.clear();
+    assert!(medication.was_active_at(now - Duration::days(1)));
+}
+
+#[test]
+fn form_schedule_repeats_every_few_days() {
+    let schedule = FormSchedule {
+        every_days: 3,
+        time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+        starts_on: NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(),
+    };
+    let from = NaiveDate::from_ymd_opt(2024, 6, 5)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc();
+
+    let due = schedule.due_between(from, from + Duration::days(7));
+
+    assert_eq!(
+        due.iter()
+            .map(|due| due.date_naive().to_string())
+            .collect::<Vec<_>>(),
+        vec!["2024-06-07", "2024-06-10"]
+    );
+    assert!(schedule
+        .due_between(from - Duration::days(30), from - Duration::days(10))
+        .is_empty());
+}
+
+#[test]
+fn scheduled_forms_become_overdue_after_the_grace_period() {
+    let user_id = ObjectId::new();
+    let mut form = Form::from(
+        ObjectId::new(),
+        String::from("Tremors"),
+        None,
+        user_id,
+        user_id,
+        vec![],
+        Some(FormSchedule {
+            every_days: 7,
+            time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            starts_on: NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(),
+        }),
+    );
+    let due = NaiveDate::from_ymd_opt(2024, 6, 8)
+        .unwrap()
+        .and_hms_opt(9, 0, 0)
+        .unwrap()
+        .and_utc();
+    let grace = Duration::hours(24);
+
+    assert_eq!(
+        form.overdue_since(due + Duration::hours(12), grace),
+        Some(due - Duration::days(7))
+    );
+    assert_eq!(
+        form.overdue_since(due + Duration::hours(25), grace),
+        Some(due)
+    );
+
+    form.events.push(Event::FormSubmitted(FormSubmitted {
+        answers: vec![],
+        submitted_by: user_id,
+        submitted_at: DateTime::from_chrono(due + Duration::hours(2)),
+    }));
+    assert_eq!(form.overdue_since(due + Duration::hours(25), grace), None);
+}
@@ -0,0 +1,58 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use mongodb::{
+    bson::{doc, DateTime},
+    Database,
+};
+
+use crate::app::{
+    auth::middleware::Auth,
+    models::{dto::notification::NotificationPath, Notification},
+};
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn dismiss(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<NotificationPath>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to dismiss notifications"),
+        )
+            .into_response();
+    };
+
+    let now = DateTime::now();
+    let result = db
+        .collection::<Notification>("notifications")
+        .update_one(
+            doc! { "_id": path.notification_id, "user_id": auth.id },
+            vec![doc! { "$set": {
+              "dismissed_at": now,
+              "read_at": { "$ifNull": ["$read_at", now] }
+            } }],
+        )
+        .await;
+    match result {
+        Ok(result) => {
+            if result.matched_count == 0 {
+                return (
+                    StatusCode::NOT_FOUND,
+                    String::from("Could not find notification"),
+                )
+                    .into_response();
+            }
+            StatusCode::OK.into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
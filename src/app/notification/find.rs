@@ -0,0 +1,95 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Bson, DateTime},
+    Database,
+};
+use serde_json::json;
+
+use crate::app::{
+    auth::middleware::Auth,
+    models::{dto::notification::FindNotificationsQuery, Notification},
+};
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 200;
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn find_notifications(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Query(query): Query<FindNotificationsQuery>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to view notifications"),
+        )
+            .into_response();
+    };
+
+    let mut filter = doc! {
+      "user_id": auth.id,
+      "dismissed_at": Bson::Null
+    };
+    if query.unread_only {
+        filter.insert("read_at", Bson::Null);
+    }
+    if let Some(before) = query.before {
+        filter.insert("created_at", doc! { "$lt": DateTime::from_chrono(before) });
+    }
+
+    let result = db
+        .collection::<Notification>("notifications")
+        .find(filter)
+        .sort(doc! { "created_at": -1 })
+        .limit(query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT))
+        .await;
+    match result {
+        Ok(cursor) => match cursor.try_collect::<Vec<Notification>>().await {
+            Ok(notifications) => (StatusCode::OK, Json(notifications)).into_response(),
+            Err(e) => {
+                tracing::error!(error = %e, "Error occurred while reading notifications");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        },
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn count_unread(State(db): State<Database>, Auth(auth): Auth) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to view notifications"),
+        )
+            .into_response();
+    };
+
+    let result = db
+        .collection::<Notification>("notifications")
+        .count_documents(doc! {
+          "user_id": auth.id,
+          "read_at": Bson::Null,
+          "dismissed_at": Bson::Null
+        })
+        .await;
+    match result {
+        Ok(unread) => (StatusCode::OK, Json(json!({ "unread": unread }))).into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -0,0 +1,47 @@
+mod dismiss;
+mod find;
+mod read;
+
+use axum::{
+    routing::{delete, get, post},
+    Router,
+};
+use dismiss::dismiss;
+use find::{count_unread, find_notifications};
+use mongodb::Database;
+use read::{mark_all_read, mark_read};
+
+use super::{
+    events::{publish, LiveEvent},
+    models::Notification,
+    AppState,
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/find", get(find_notifications))
+        .route("/unread", get(count_unread))
+        .route("/read/:notification_id", post(mark_read))
+        .route("/read", post(mark_all_read))
+        .route("/dismiss/:notification_id", delete(dismiss))
+}
+
+/// Adds a notification to a user's inbox. This is best effort, so failures are logged rather
+/// than stopping whatever caused the notification.
+pub async fn notify(db: &Database, notification: Notification) {
+    let result = db
+        .collection::<Notification>("notifications")
+        .insert_one(&notification)
+        .await;
+    match result {
+        Ok(..) => {
+            publish(
+                db,
+                vec![notification.user_id],
+                LiveEvent::Notification(notification),
+            )
+            .await;
+        }
+        Err(e) => tracing::error!(error = %e, "Failed to add notification"),
+    }
+}
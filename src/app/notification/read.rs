@@ -0,0 +1,82 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use mongodb::{
+    bson::{doc, Bson, DateTime},
+    Database,
+};
+
+use crate::app::{
+    auth::middleware::Auth,
+    models::{dto::notification::NotificationPath, Notification},
+};
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn mark_read(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<NotificationPath>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to read notifications"),
+        )
+            .into_response();
+    };
+
+    let result = db
+        .collection::<Notification>("notifications")
+        .update_one(
+            doc! { "_id": path.notification_id, "user_id": auth.id },
+            // Keep the time it was first read
+            vec![doc! { "$set": { "read_at": { "$ifNull": ["$read_at", DateTime::now()] } } }],
+        )
+        .await;
+    match result {
+        Ok(result) => {
+            if result.matched_count == 0 {
+                return (
+                    StatusCode::NOT_FOUND,
+                    String::from("Could not find notification"),
+                )
+                    .into_response();
+            }
+            StatusCode::OK.into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn mark_all_read(State(db): State<Database>, Auth(auth): Auth) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to read notifications"),
+        )
+            .into_response();
+    };
+
+    let result = db
+        .collection::<Notification>("notifications")
+        .update_many(
+            doc! { "user_id": auth.id, "read_at": Bson::Null },
+            doc! { "$set": { "read_at": DateTime::now() } },
+        )
+        .await;
+    match result {
+        Ok(..) => StatusCode::OK.into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use mongodb::{
+    bson::{doc, oid::ObjectId, DateTime},
+    Database,
+};
+use serde_json::json;
+
+use super::require_organisation_admin;
+use crate::app::{
+    auth::middleware::Auth,
+    models::{
+        dto::organisation::{AssignClinicianPayload, AssignmentPath, OrganisationPath},
+        CaregiverRequest, CaregiverRequestStatus, CaregiverScope, Notification, NotificationKind,
+        Role, User,
+    },
+    notification::notify,
+};
+
+/// Asks a patient to make a clinician one of their caregivers. Both must be members of the
+/// organisation, and the clinician only gets access once the patient approves it.
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn assign_clinician(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<OrganisationPath>,
+    Json(payload): Json<AssignClinicianPayload>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to assign clinicians"),
+        )
+            .into_response();
+    };
+
+    let organisation = match require_organisation_admin(&db, auth.id, path.organisation_id).await {
+        Ok(organisation) => organisation,
+        Err(response) => return response,
+    };
+
+    let users = db.collection::<User>("users");
+    let patient = users
+        .find_one(doc! { "_id": payload.patient_id, "organisation_id": organisation.id })
+        .await;
+    let clinician = users
+        .find_one(doc! { "_id": payload.clinician_id, "organisation_id": organisation.id })
+        .await;
+    let (patient, clinician) = match (patient, clinician) {
+        (Ok(Some(patient)), Ok(Some(clinician)))
+            if patient.role() == Role::Patient && clinician.role() == Role::Clinician =>
+        {
+            (patient, clinician)
+        }
+        (Ok(..), Ok(..)) => {
+            return (
+                StatusCode::NOT_FOUND,
+                String::from("Could not find that patient and clinician in this organisation"),
+            )
+                .into_response()
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    if patient.caregiver_grant(payload.clinician_id).is_some() {
+        return (
+            StatusCode::CONFLICT,
+            String::from("This clinician is already one of the patient's caregivers"),
+        )
+            .into_response();
+    }
+
+    let requests = db.collection::<CaregiverRequest>("caregiver_requests");
+    match requests
+        .find_one(doc! {
+          "patient_id": payload.patient_id,
+          "caregiver_id": payload.clinician_id,
+          "status": "Pending"
+        })
+        .await
+    {
+        Ok(None) => {}
+        Ok(Some(..)) => {
+            return (
+                StatusCode::CONFLICT,
+                String::from("This clinician is already waiting for the patient's approval"),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    }
+
+    let request = CaregiverRequest {
+        id: ObjectId::new(),
+        patient_id: payload.patient_id,
+        token_id: None,
+        caregiver_id: payload.clinician_id,
+        caregiver_first_name: clinician.first_name.clone(),
+        caregiver_last_name: clinician.last_name.clone(),
+        caregiver_email_address: clinician.email_address.clone(),
+        scopes: payload
+            .scopes
+            .unwrap_or_else(|| CaregiverScope::DEFAULT.to_vec()),
+        access_expires_at: None,
+        status: CaregiverRequestStatus::Pending,
+        requested_at: DateTime::now(),
+        decided_at: None,
+    };
+    let request_id = request.id;
+    if let Err(e) = requests.insert_one(request).await {
+        tracing::error!(error = %e, "Error occurred while querying database");
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    notify(
+        &db,
+        Notification::from(
+            payload.patient_id,
+            NotificationKind::ClinicianAssigned {
+                organisation_id: organisation.id,
+                clinician_id: payload.clinician_id,
+                request_id,
+            },
+            String::from("New clinician"),
+            format!(
+                "{} has asked for {} {} to become one of your caregivers",
+                organisation.name, clinician.first_name, clinician.last_name
+            ),
+        ),
+    )
+    .await;
+    (
+        StatusCode::ACCEPTED,
+        Json(json!({ "request_id": request_id })),
+    )
+        .into_response()
+}
+
+/// Stops a clinician being one of a patient's caregivers
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn unassign_clinician(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<AssignmentPath>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to unassign clinicians"),
+        )
+            .into_response();
+    };
+
+    if let Err(response) = require_organisation_admin(&db, auth.id, path.organisation_id).await {
+        return response;
+    }
+
+    // Caregivers from outside the organisation, such as family, are left for the patient to manage
+    let users = db.collection::<User>("users");
+    match users
+        .find_one(doc! { "_id": path.clinician_id, "organisation_id": path.organisation_id })
+        .await
+    {
+        Ok(Some(..)) => {}
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                String::from("Could not find that clinician in this organisation"),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    }
+
+    let result = users
+        .update_one(
+            doc! { "_id": path.patient_id, "organisation_id": path.organisation_id },
+            doc! { "$pull": { "caregivers": { "caregiver_id": path.clinician_id } } },
+        )
+        .await;
+    match result {
+        Ok(result) if result.modified_count == 0 => (
+            StatusCode::NOT_FOUND,
+            String::from("This clinician is not assigned to the patient"),
+        )
+            .into_response(),
+        Ok(..) => StatusCode::OK.into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use mongodb::{
+    bson::{oid::ObjectId, DateTime},
+    Database,
+};
+use serde_json::json;
+
+use crate::app::{
+    auth::{middleware::Auth, role::require_role},
+    models::{dto::organisation::CreateOrganisationPayload, Organisation, Role},
+};
+
+/// Creates an organisation with no members. Only site admins can do this.
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn create_organisation(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Json(payload): Json<CreateOrganisationPayload>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to create an organisation"),
+        )
+            .into_response();
+    };
+
+    if let Err(response) = require_role(&db, auth.id, &[Role::Admin]).await {
+        return response;
+    }
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            String::from("Organisations must have a name"),
+        )
+            .into_response();
+    }
+
+    let organisation = Organisation {
+        id: ObjectId::new(),
+        name: name.to_string(),
+        admins: vec![],
+        created_at: DateTime::now(),
+    };
+    match db
+        .collection::<Organisation>("organisations")
+        .insert_one(&organisation)
+        .await
+    {
+        Ok(..) => (
+            StatusCode::CREATED,
+            Json(json!({ "organisation_id": organisation.id })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, to_bson},
+    Database,
+};
+
+use super::members::enrolment_role;
+use crate::app::{
+    auth::middleware::Auth,
+    models::{
+        dto::organisation::OrganisationInvitationPath, Organisation, OrganisationInvitation,
+        OrganisationRole, User,
+    },
+};
+
+/// Lists the organisations that have invited the signed-in user to join them
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn find_invitations(State(db): State<Database>, Auth(auth): Auth) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to view organisation invitations"),
+        )
+            .into_response();
+    };
+
+    let result = db
+        .collection::<OrganisationInvitation>("organisation_invitations")
+        .find(doc! { "user_id": auth.id })
+        .sort(doc! { "created_at": -1 })
+        .await;
+    match result {
+        Ok(cursor) => match cursor.try_collect::<Vec<OrganisationInvitation>>().await {
+            Ok(invitations) => (StatusCode::OK, Json(invitations)).into_response(),
+            Err(e) => {
+                tracing::error!(error = %e, "Error occurred while reading organisation invitations");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        },
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Enrols the signed-in user in the organisation that invited them
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn accept_invitation(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<OrganisationInvitationPath>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to join an organisation"),
+        )
+            .into_response();
+    };
+
+    let invitations = db.collection::<OrganisationInvitation>("organisation_invitations");
+    let invitation = match invitations
+        .find_one(doc! { "_id": path.invitation_id, "user_id": auth.id })
+        .await
+    {
+        Ok(Some(invitation)) => invitation,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                String::from("Could not find that invitation"),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let users = db.collection::<User>("users");
+    let user = match users.find_one(doc! { "_id": auth.id }).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, String::from("Could not find user")).into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let mut update = doc! { "organisation_id": invitation.organisation_id };
+    match enrolment_role(&user, invitation.role) {
+        Ok(Some(role)) => {
+            let Ok(role) = to_bson(&role) else {
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            };
+            update.insert("role", role);
+        }
+        Ok(None) => {}
+        Err(e) => return e.into_response(),
+    }
+
+    // Checked in the update so two organisations can't enrol the same user at once
+    let result = users
+        .update_one(
+            doc! {
+              "_id": auth.id,
+              "organisation_id": { "$in": [null, invitation.organisation_id] }
+            },
+            doc! { "$set": update },
+        )
+        .await;
+    match result {
+        Ok(result) if result.matched_count == 0 => {
+            return (
+                StatusCode::CONFLICT,
+                String::from("You already belong to another organisation"),
+            )
+                .into_response()
+        }
+        Ok(..) => {}
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    }
+
+    if invitation.role == OrganisationRole::Admin {
+        if let Err(e) = db
+            .collection::<Organisation>("organisations")
+            .update_one(
+                doc! { "_id": invitation.organisation_id },
+                doc! { "$addToSet": { "admins": auth.id } },
+            )
+            .await
+        {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    }
+
+    if let Err(e) = invitations.delete_one(doc! { "_id": invitation.id }).await {
+        tracing::error!(error = %e, "Error occurred while querying database");
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+    StatusCode::OK.into_response()
+}
+
+/// Turns down an invitation to join an organisation
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn decline_invitation(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<OrganisationInvitationPath>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to decline an organisation invitation"),
+        )
+            .into_response();
+    };
+
+    let result = db
+        .collection::<OrganisationInvitation>("organisation_invitations")
+        .delete_one(doc! { "_id": path.invitation_id, "user_id": auth.id })
+        .await;
+    match result {
+        Ok(result) if result.deleted_count == 0 => (
+            StatusCode::NOT_FOUND,
+            String::from("Could not find that invitation"),
+        )
+            .into_response(),
+        Ok(..) => StatusCode::OK.into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -0,0 +1,342 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, DateTime},
+    Database,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::require_organisation_admin;
+use crate::app::{
+    auth::{middleware::Auth, role::require_role},
+    models::{
+        dto::organisation::{EnrolPayload, OrganisationMemberPath, OrganisationPath},
+        Notification, NotificationKind, Organisation, OrganisationInvitation, OrganisationRole,
+        Role, User,
+    },
+    notification::notify,
+};
+
+#[derive(Serialize, Deserialize)]
+struct MemberInfo {
+    #[serde(rename = "_id")]
+    id: ObjectId,
+    first_name: String,
+    last_name: String,
+    email_address: String,
+    role: Role,
+    /// Whether they can manage the organisation
+    is_admin: bool,
+}
+
+/// Lists everyone enrolled in an organisation
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn find_members(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<OrganisationPath>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to find members of an organisation"),
+        )
+            .into_response();
+    };
+
+    let organisation = match require_organisation_admin(&db, auth.id, path.organisation_id).await {
+        Ok(organisation) => organisation,
+        Err(response) => return response,
+    };
+
+    let result = db
+        .collection::<User>("users")
+        .find(doc! { "organisation_id": organisation.id })
+        .sort(doc! { "last_name": 1, "first_name": 1 })
+        .await;
+    let users = match result {
+        Ok(cursor) => cursor.try_collect::<Vec<User>>().await,
+        Err(e) => Err(e),
+    };
+    match users {
+        Ok(users) => {
+            let members = users
+                .into_iter()
+                .filter_map(|user| {
+                    Some(MemberInfo {
+                        id: user.id?,
+                        role: user.role(),
+                        is_admin: user.id.is_some_and(|id| organisation.admins.contains(&id)),
+                        first_name: user.first_name,
+                        last_name: user.last_name,
+                        email_address: user.email_address,
+                    })
+                })
+                .collect::<Vec<_>>();
+            (StatusCode::OK, Json(members)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Invites a user to join an organisation. Users can only belong to one organisation at a time, and
+/// are only enrolled once they accept.
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn enrol_member(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<OrganisationPath>,
+    Json(payload): Json<EnrolPayload>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to enrol members of an organisation"),
+        )
+            .into_response();
+    };
+
+    let organisation = match require_organisation_admin(&db, auth.id, path.organisation_id).await {
+        Ok(organisation) => organisation,
+        Err(response) => return response,
+    };
+    if payload.role == OrganisationRole::Admin {
+        if let Err(response) = require_role(&db, auth.id, &[Role::Admin]).await {
+            return response;
+        }
+    }
+
+    let user = match db
+        .collection::<User>("users")
+        .find_one(doc! { "email_address": &payload.email_address })
+        .await
+    {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                String::from("Could not find a user with that email address"),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let Some(user_id) = user.id else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+
+    if let Err(e) = enrolment_role(&user, payload.role) {
+        return e.into_response();
+    }
+    if user
+        .organisation_id
+        .is_some_and(|organisation_id| organisation_id != organisation.id)
+    {
+        return (
+            StatusCode::CONFLICT,
+            String::from("This user already belongs to another organisation"),
+        )
+            .into_response();
+    }
+
+    // A new invitation replaces any the user hasn't answered yet
+    let invitations = db.collection::<OrganisationInvitation>("organisation_invitations");
+    if let Err(e) = invitations
+        .delete_many(doc! { "organisation_id": organisation.id, "user_id": user_id })
+        .await
+    {
+        tracing::error!(error = %e, "Error occurred while querying database");
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+    let invitation = OrganisationInvitation {
+        id: ObjectId::new(),
+        organisation_id: organisation.id,
+        organisation_name: organisation.name.clone(),
+        user_id,
+        role: payload.role,
+        invited_by: auth.id,
+        created_at: DateTime::now(),
+    };
+    let invitation_id = invitation.id;
+    if let Err(e) = invitations.insert_one(invitation).await {
+        tracing::error!(error = %e, "Error occurred while querying database");
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    notify(
+        &db,
+        Notification::from(
+            user_id,
+            NotificationKind::OrganisationInvitation {
+                organisation_id: organisation.id,
+                invitation_id,
+            },
+            String::from("Organisation invitation"),
+            format!(
+                "{} has invited you to join it as {}",
+                organisation.name,
+                match payload.role {
+                    OrganisationRole::Patient => "a patient",
+                    OrganisationRole::Clinician => "a clinician",
+                    OrganisationRole::Admin => "an admin",
+                }
+            ),
+        ),
+    )
+    .await;
+    (
+        StatusCode::ACCEPTED,
+        Json(json!({ "invitation_id": invitation_id })),
+    )
+        .into_response()
+}
+
+/// Why a user can't be enrolled in an organisation as a role
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnrolmentError {
+    NotPatient,
+    IsPatient,
+    /// Site admins keep their role, so they can't be enrolled as clinicians
+    SiteAdmin,
+}
+
+impl IntoResponse for EnrolmentError {
+    fn into_response(self) -> Response {
+        let message = match self {
+            EnrolmentError::NotPatient => "Only patients can be enrolled as patients",
+            EnrolmentError::IsPatient => "Patients cannot be enrolled as clinicians",
+            EnrolmentError::SiteAdmin => "Site admins cannot be enrolled as clinicians",
+        };
+        (StatusCode::BAD_REQUEST, String::from(message)).into_response()
+    }
+}
+
+/// The role a user will be given when they are enrolled as `role`, if it changes
+///
+/// # Errors
+/// Returns why the user can't be enrolled as `role`
+pub fn enrolment_role(user: &User, role: OrganisationRole) -> Result<Option<Role>, EnrolmentError> {
+    match (role, user.role()) {
+        (OrganisationRole::Patient, Role::Patient) => Ok(None),
+        (OrganisationRole::Patient, ..) => Err(EnrolmentError::NotPatient),
+        (OrganisationRole::Clinician, Role::Patient) => Err(EnrolmentError::IsPatient),
+        (OrganisationRole::Clinician, Role::Admin) => Err(EnrolmentError::SiteAdmin),
+        (OrganisationRole::Clinician, ..) => Ok(Some(Role::Clinician)),
+        (OrganisationRole::Admin, ..) => Ok(None),
+    }
+}
+
+/// Takes a user out of an organisation, along with any access between them and its other members
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn remove_member(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<OrganisationMemberPath>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to remove members of an organisation"),
+        )
+            .into_response();
+    };
+
+    if let Err(response) = require_organisation_admin(&db, auth.id, path.organisation_id).await {
+        return response;
+    }
+
+    match unenrol(&db, path.organisation_id, path.user_id).await {
+        Ok(true) => StatusCode::OK.into_response(),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            String::from("This user is not a member of the organisation"),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Removes a user from an organisation
+///
+/// Returns whether they were a member
+async fn unenrol(
+    db: &Database,
+    organisation_id: ObjectId,
+    user_id: ObjectId,
+) -> mongodb::error::Result<bool> {
+    let users = db.collection::<User>("users");
+    let member_ids = users
+        .distinct("_id", doc! { "organisation_id": organisation_id })
+        .await?;
+
+    let result = users
+        .update_one(
+            doc! { "_id": user_id, "organisation_id": organisation_id },
+            doc! {
+              "$unset": { "organisation_id": "" },
+              "$pull": { "caregivers": { "caregiver_id": { "$in": member_ids } } }
+            },
+        )
+        .await?;
+    if result.matched_count == 0 {
+        return Ok(false);
+    }
+
+    users
+        .update_many(
+            doc! { "organisation_id": organisation_id },
+            doc! { "$pull": { "caregivers": { "caregiver_id": user_id } } },
+        )
+        .await?;
+    db.collection::<Organisation>("organisations")
+        .update_one(
+            doc! { "_id": organisation_id },
+            doc! { "$pull": { "admins": user_id } },
+        )
+        .await?;
+    Ok(true)
+}
+
+#[test]
+fn site_admins_keep_their_role() {
+    let mut user = User::from(
+        String::from("Ad"),
+        String::from("Min"),
+        String::from("admin@example.com"),
+        String::new(),
+        false,
+    );
+    assert_eq!(
+        enrolment_role(&user, OrganisationRole::Clinician),
+        Ok(Some(Role::Clinician))
+    );
+
+    user.role = Some(Role::Admin);
+    assert_eq!(
+        enrolment_role(&user, OrganisationRole::Clinician),
+        Err(EnrolmentError::SiteAdmin)
+    );
+    assert_eq!(enrolment_role(&user, OrganisationRole::Admin), Ok(None));
+    assert_eq!(
+        enrolment_role(&user, OrganisationRole::Patient),
+        Err(EnrolmentError::NotPatient)
+    );
+}
@@ -0,0 +1,96 @@
+mod assignments;
+mod create;
+mod invitations;
+mod members;
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Router,
+};
+use mongodb::{
+    bson::{doc, oid::ObjectId},
+    Database,
+};
+
+use super::{
+    models::{Organisation, Role, User},
+    AppState,
+};
+
+/// Finds an organisation and checks that the user is one of its admins, or a site admin
+async fn require_organisation_admin(
+    db: &Database,
+    user_id: ObjectId,
+    organisation_id: ObjectId,
+) -> Result<Organisation, Response> {
+    let organisation = match db
+        .collection::<Organisation>("organisations")
+        .find_one(doc! { "_id": organisation_id })
+        .await
+    {
+        Ok(Some(organisation)) => organisation,
+        Ok(None) => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                String::from("Could not find organisation"),
+            )
+                .into_response())
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        }
+    };
+    if organisation.admins.contains(&user_id) {
+        return Ok(organisation);
+    }
+
+    match db
+        .collection::<User>("users")
+        .find_one(doc! { "_id": user_id })
+        .await
+    {
+        Ok(Some(user)) if user.role() == Role::Admin => Ok(organisation),
+        Ok(..) => Err((
+            StatusCode::FORBIDDEN,
+            String::from("Only admins of this organisation can do this"),
+        )
+            .into_response()),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/create", post(create::create_organisation))
+        .route("/invitations", get(invitations::find_invitations))
+        .route(
+            "/invitations/:invitation_id",
+            delete(invitations::decline_invitation),
+        )
+        .route(
+            "/invitations/:invitation_id/accept",
+            post(invitations::accept_invitation),
+        )
+        .route(
+            "/:organisation_id/members",
+            get(members::find_members).post(members::enrol_member),
+        )
+        .route(
+            "/:organisation_id/members/:user_id",
+            delete(members::remove_member),
+        )
+        .route(
+            "/:organisation_id/assignments",
+            post(assignments::assign_clinician),
+        )
+        .route(
+            "/:organisation_id/assignments/:patient_id/:clinician_id",
+            delete(assignments::unassign_clinician),
+        )
+}
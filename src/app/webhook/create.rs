@@ -0,0 +1,76 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use mongodb::{
+    bson::{oid::ObjectId, DateTime},
+    Database,
+};
+use serde_json::json;
+
+use crate::app::{
+    auth::middleware::Auth,
+    models::{dto::webhook::CreateWebhookPayload, generate_random_string, WebhookSubscription},
+    webhook::target::check_target,
+};
+
+const SECRET_LENGTH: usize = 32;
+
+#[tracing::instrument(skip(payload))]
+#[axum::debug_handler]
+pub async fn create_webhook(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Json(payload): Json<CreateWebhookPayload>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to create a webhook"),
+        )
+            .into_response();
+    };
+
+    if let Err(e) = check_target(&payload.url).await {
+        return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
+    }
+    if payload.event_types.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            String::from("Webhook must be subscribed to at least one event type"),
+        )
+            .into_response();
+    }
+
+    let id = ObjectId::new();
+    let secret = payload
+        .secret
+        .filter(|secret| !secret.is_empty())
+        .unwrap_or_else(|| generate_random_string(SECRET_LENGTH));
+    let subscription = WebhookSubscription {
+        id,
+        user_id: auth.id,
+        url: payload.url,
+        event_types: payload.event_types,
+        secret: secret.clone(),
+        created_at: DateTime::now(),
+    };
+    let result = db
+        .collection::<WebhookSubscription>("webhooks")
+        .insert_one(subscription)
+        .await;
+    match result {
+        // The secret is only ever shown here
+        Ok(..) => (
+            StatusCode::OK,
+            Json(json!({ "created_id": id, "secret": secret })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
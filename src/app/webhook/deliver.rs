@@ -0,0 +1,231 @@
+use std::{
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use axum::http::header::CONTENT_TYPE;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson, DateTime},
+    Database,
+};
+use reqwest::{redirect::Policy, Url};
+use sha2::Sha256;
+
+use super::target::{check_target, PublicResolver};
+use crate::app::models::{DeliveryAttempt, DeliveryStatus, WebhookDelivery, WebhookSubscription};
+
+pub const SIGNATURE_HEADER: &str = "x-pulse-signature";
+pub const TIMESTAMP_HEADER: &str = "x-pulse-timestamp";
+pub const EVENT_HEADER: &str = "x-pulse-event";
+pub const DELIVERY_HEADER: &str = "x-pulse-delivery";
+
+const TIMEOUT_SECONDS: u64 = 10;
+
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            // A redirect could lead somewhere the URL check never saw
+            .redirect(Policy::none())
+            .dns_resolver(Arc::new(PublicResolver))
+            .build()
+            .expect("HTTP client settings are valid")
+    })
+}
+
+/// Signs a payload as `sha256=<hex HMAC-SHA256 of "<timestamp>.<payload>">`. Receivers should
+/// compute the same and reject requests that don't match or have an old timestamp.
+///
+/// # Panics
+/// Never, as HMAC accepts keys of any length
+#[must_use]
+pub fn sign(secret: &str, timestamp: i64, payload: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(payload.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Makes one attempt to send a delivery to a webhook
+pub async fn send(
+    subscription: &WebhookSubscription,
+    delivery: &WebhookDelivery,
+) -> DeliveryAttempt {
+    match check_target(&subscription.url).await {
+        Ok(url) => post_signed(url, &subscription.secret, delivery).await,
+        Err(e) => DeliveryAttempt {
+            attempted_at: DateTime::now(),
+            status_code: None,
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+/// Posts a delivery to a URL that has already been checked, signed with `secret`
+async fn post_signed(url: Url, secret: &str, delivery: &WebhookDelivery) -> DeliveryAttempt {
+    let timestamp = Utc::now().timestamp();
+    let result = client()
+        .post(url)
+        .timeout(Duration::from_secs(TIMEOUT_SECONDS))
+        .header(CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, format!("{:?}", delivery.event_type))
+        .header(DELIVERY_HEADER, delivery.id.to_hex())
+        .header(TIMESTAMP_HEADER, timestamp)
+        .header(SIGNATURE_HEADER, sign(secret, timestamp, &delivery.payload))
+        .body(delivery.payload.clone())
+        .send()
+        .await;
+    match result {
+        Ok(response) => {
+            let status = response.status();
+            DeliveryAttempt {
+                attempted_at: DateTime::now(),
+                status_code: Some(status.as_u16()),
+                error: (!status.is_success()).then(|| format!("Receiver responded with {status}")),
+            }
+        }
+        Err(e) => DeliveryAttempt {
+            attempted_at: DateTime::now(),
+            status_code: None,
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+/// Sends a stored delivery that hasn't been delivered yet, and records the attempt
+///
+/// # Errors
+/// Returns an error if the delivery failed, so that it can be retried
+pub async fn attempt_delivery(db: &Database, delivery_id: ObjectId) -> anyhow::Result<()> {
+    let deliveries = db.collection::<WebhookDelivery>("webhook_deliveries");
+    let Some(delivery) = deliveries
+        .find_one(doc! { "_id": delivery_id, "status": "Pending" })
+        .await?
+    else {
+        return Ok(());
+    };
+    let Some(subscription) = db
+        .collection::<WebhookSubscription>("webhooks")
+        .find_one(doc! { "_id": delivery.webhook_id })
+        .await?
+    else {
+        // The webhook was removed, so there is nowhere to send it
+        set_delivery_status(db, delivery_id, DeliveryStatus::Failed).await?;
+        return Ok(());
+    };
+
+    let attempt = send(&subscription, &delivery).await;
+    let error = attempt.error.clone();
+    let status = if error.is_none() {
+        DeliveryStatus::Delivered
+    } else {
+        DeliveryStatus::Pending
+    };
+    deliveries
+        .update_one(
+            doc! { "_id": delivery_id },
+            doc! {
+              "$push": { "attempts": to_bson(&attempt)? },
+              "$set": { "status": to_bson(&status)? }
+            },
+        )
+        .await?;
+
+    match error {
+        Some(error) => Err(anyhow::anyhow!(error)),
+        None => Ok(()),
+    }
+}
+
+/// Records that a delivery was given up on, or has finished
+///
+/// # Errors
+/// Returns an error if the database could not be updated
+pub async fn set_delivery_status(
+    db: &Database,
+    delivery_id: ObjectId,
+    status: DeliveryStatus,
+) -> mongodb::error::Result<()> {
+    db.collection::<WebhookDelivery>("webhook_deliveries")
+        .update_one(
+            doc! { "_id": delivery_id },
+            doc! { "$set": { "status": to_bson(&status)? } },
+        )
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn sends_signed_payloads() {
+    use axum::{
+        extract::State,
+        http::{HeaderMap, StatusCode},
+        routing::post,
+        Router,
+    };
+    use tokio::sync::mpsc;
+
+    // A receiver that checks signatures the way an integrator would
+    const SECRET: &str = "shh";
+    let (sender, mut receiver) = mpsc::unbounded_channel::<String>();
+    let stub = Router::new()
+        .route(
+            "/hook",
+            post(
+                |State(sender): State<mpsc::UnboundedSender<String>>,
+                 headers: HeaderMap,
+                 body: String| async move {
+                    let timestamp = headers[TIMESTAMP_HEADER].to_str().unwrap();
+                    let mut mac = Hmac::<Sha256>::new_from_slice(SECRET.as_bytes()).unwrap();
+                    mac.update(format!("{timestamp}.{body}").as_bytes());
+                    let expected = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+                    if headers[SIGNATURE_HEADER] != expected.as_str() {
+                        return StatusCode::UNAUTHORIZED;
+                    }
+                    sender.send(body).unwrap();
+                    StatusCode::OK
+                },
+            ),
+        )
+        .with_state(sender);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, stub).await.unwrap() });
+
+    let subscription = WebhookSubscription {
+        id: ObjectId::new(),
+        user_id: ObjectId::new(),
+        url: format!("http://{addr}/hook"),
+        event_types: vec![crate::app::models::WebhookEventType::Ping],
+        secret: String::from(SECRET),
+        created_at: DateTime::now(),
+    };
+    let delivery = WebhookDelivery {
+        id: ObjectId::new(),
+        webhook_id: subscription.id,
+        event_type: crate::app::models::WebhookEventType::Ping,
+        payload: String::from(r#"{"message":"Hello"}"#),
+        status: DeliveryStatus::Pending,
+        attempts: Vec::new(),
+        created_at: DateTime::now(),
+    };
+
+    // Webhooks can't be pointed at the server itself
+    let attempt = send(&subscription, &delivery).await;
+    assert_eq!(attempt.status_code, None);
+    assert!(attempt.error.is_some());
+
+    let url = Url::parse(&subscription.url).unwrap();
+    let attempt = post_signed(url.clone(), SECRET, &delivery).await;
+    assert_eq!(attempt.status_code, Some(200));
+    assert!(attempt.error.is_none());
+    assert_eq!(receiver.recv().await.unwrap(), delivery.payload);
+
+    let attempt = post_signed(url, "wrong", &delivery).await;
+    assert_eq!(attempt.status_code, Some(401));
+    assert!(attempt.error.is_some());
+}
@@ -0,0 +1,114 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::TryStreamExt;
+use mongodb::{bson::doc, Database};
+
+use crate::app::{
+    auth::middleware::Auth,
+    models::{
+        dto::webhook::{WebhookPath, WebhookResponse},
+        WebhookDelivery, WebhookSubscription,
+    },
+};
+
+/// Most deliveries returned in a webhook's delivery log
+const MAX_DELIVERIES: i64 = 100;
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn find_webhooks(State(db): State<Database>, Auth(auth): Auth) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to view webhooks"),
+        )
+            .into_response();
+    };
+
+    let result = db
+        .collection::<WebhookSubscription>("webhooks")
+        .find(doc! { "user_id": auth.id })
+        .await;
+    match result {
+        Ok(cursor) => match cursor.try_collect::<Vec<WebhookSubscription>>().await {
+            Ok(subscriptions) => (
+                StatusCode::OK,
+                Json(
+                    subscriptions
+                        .into_iter()
+                        .map(WebhookResponse::from)
+                        .collect::<Vec<_>>(),
+                ),
+            )
+                .into_response(),
+            Err(e) => {
+                tracing::error!(error = %e, "Error occurred while reading webhooks");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        },
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Lists the most recent deliveries to a webhook, newest first
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn find_deliveries(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<WebhookPath>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to view webhooks"),
+        )
+            .into_response();
+    };
+
+    match db
+        .collection::<WebhookSubscription>("webhooks")
+        .find_one(doc! { "_id": path.webhook_id, "user_id": auth.id })
+        .await
+    {
+        Ok(Some(..)) => {}
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                String::from("Could not find webhook"),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    }
+
+    let result = db
+        .collection::<WebhookDelivery>("webhook_deliveries")
+        .find(doc! { "webhook_id": path.webhook_id })
+        .sort(doc! { "created_at": -1 })
+        .limit(MAX_DELIVERIES)
+        .await;
+    match result {
+        Ok(cursor) => match cursor.try_collect::<Vec<WebhookDelivery>>().await {
+            Ok(deliveries) => (StatusCode::OK, Json(deliveries)).into_response(),
+            Err(e) => {
+                tracing::error!(error = %e, "Error occurred while reading webhook deliveries");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        },
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
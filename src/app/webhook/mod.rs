@@ -0,0 +1,117 @@
+mod create;
+pub mod deliver;
+mod find;
+mod ping;
+mod remove;
+pub mod target;
+
+use axum::{
+    routing::{delete, get, post},
+    Router,
+};
+use create::create_webhook;
+use find::{find_deliveries, find_webhooks};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson, DateTime},
+    Database,
+};
+use ping::ping;
+use remove::remove_webhook;
+use serde_json::json;
+
+use super::{
+    events::Envelope,
+    models::{
+        DeliveryStatus, Job, JobKind, WebhookDelivery, WebhookEventType, WebhookSubscription,
+    },
+    AppState,
+};
+use crate::scheduler::insert_job;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/create", post(create_webhook))
+        .route("/find", get(find_webhooks))
+        .route("/deliveries/:webhook_id", get(find_deliveries))
+        .route("/ping/:webhook_id", post(ping))
+        .route("/remove/:webhook_id", delete(remove_webhook))
+}
+
+/// Creates a delivery that hasn't been sent yet
+fn new_delivery(
+    webhook_id: ObjectId,
+    event_type: WebhookEventType,
+    created_at: DateTime,
+    data: &impl serde::Serialize,
+) -> WebhookDelivery {
+    let id = ObjectId::new();
+    let payload = json!({
+        "id": id.to_hex(),
+        "event_type": event_type,
+        "created_at": created_at.try_to_rfc3339_string().ok(),
+        "data": data,
+    });
+    WebhookDelivery {
+        id,
+        webhook_id,
+        event_type,
+        payload: payload.to_string(),
+        status: DeliveryStatus::Pending,
+        attempts: Vec::new(),
+        created_at,
+    }
+}
+
+/// Queues an event to be sent to every webhook subscribed to it whose owner may see it. The
+/// scheduler sends them, retrying with exponential backoff if they fail.
+pub(crate) async fn enqueue_deliveries(db: &Database, envelope: &Envelope) {
+    let event_type = envelope.event.webhook_event_type();
+    let Ok(event_type_bson) = to_bson(&event_type) else {
+        return;
+    };
+    let subscriptions = match db
+        .collection::<WebhookSubscription>("webhooks")
+        .find(doc! {
+          "user_id": { "$in": &envelope.audience },
+          "event_types": event_type_bson
+        })
+        .await
+    {
+        Ok(cursor) => cursor.try_collect::<Vec<_>>().await,
+        Err(e) => Err(e),
+    };
+    let subscriptions = match subscriptions {
+        Ok(subscriptions) => subscriptions,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to find webhooks to send event to");
+            return;
+        }
+    };
+
+    for subscription in subscriptions {
+        let delivery = new_delivery(
+            subscription.id,
+            event_type,
+            envelope.created_at,
+            &envelope.event,
+        );
+        let delivery_id = delivery.id;
+        let result = db
+            .collection::<WebhookDelivery>("webhook_deliveries")
+            .insert_one(delivery)
+            .await;
+        if let Err(e) = result {
+            tracing::error!(error = %e, "Failed to store webhook delivery");
+            continue;
+        }
+        let job = Job::from(
+            format!("webhook-delivery:{delivery_id}"),
+            JobKind::WebhookDelivery { delivery_id },
+            DateTime::now(),
+        );
+        if let Err(e) = insert_job(db, job).await {
+            tracing::error!(error = %e, "Failed to queue webhook delivery");
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use mongodb::{
+    bson::{doc, DateTime},
+    Database,
+};
+use serde_json::json;
+
+use crate::app::{
+    auth::middleware::Auth,
+    models::{
+        dto::webhook::WebhookPath, DeliveryStatus, WebhookDelivery, WebhookEventType,
+        WebhookSubscription,
+    },
+    webhook::{deliver::send, new_delivery},
+};
+
+/// Sends a test event to a webhook straight away, without retrying, and reports how it went
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn ping(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<WebhookPath>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to test a webhook"),
+        )
+            .into_response();
+    };
+
+    let subscription = match db
+        .collection::<WebhookSubscription>("webhooks")
+        .find_one(doc! { "_id": path.webhook_id, "user_id": auth.id })
+        .await
+    {
+        Ok(Some(subscription)) => subscription,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                String::from("Could not find webhook"),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let mut delivery = new_delivery(
+        subscription.id,
+        WebhookEventType::Ping,
+        DateTime::now(),
+        &json!({ "message": "This is a test event from Parkinson's Pulse" }),
+    );
+    let attempt = send(&subscription, &delivery).await;
+    delivery.status = if attempt.error.is_none() {
+        DeliveryStatus::Delivered
+    } else {
+        DeliveryStatus::Failed
+    };
+    delivery.attempts.push(attempt.clone());
+
+    let result = db
+        .collection::<WebhookDelivery>("webhook_deliveries")
+        .insert_one(&delivery)
+        .await;
+    if let Err(e) = result {
+        tracing::error!(error = %e, "Failed to store webhook delivery");
+    }
+
+    (
+        StatusCode::OK,
+        Json(json!({
+          "delivery_id": delivery.id,
+          "delivered": attempt.error.is_none(),
+          "status_code": attempt.status_code,
+          "error": attempt.error
+        })),
+    )
+        .into_response()
+}
@@ -0,0 +1,48 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use mongodb::{bson::doc, Database};
+
+use crate::app::{
+    auth::middleware::Auth,
+    models::{dto::webhook::WebhookPath, WebhookSubscription},
+};
+
+#[tracing::instrument]
+#[axum::debug_handler]
+pub async fn remove_webhook(
+    State(db): State<Database>,
+    Auth(auth): Auth,
+    Path(path): Path<WebhookPath>,
+) -> Response {
+    let Some(auth) = auth else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            String::from("You must be signed in to remove a webhook"),
+        )
+            .into_response();
+    };
+
+    let result = db
+        .collection::<WebhookSubscription>("webhooks")
+        .delete_one(doc! { "_id": path.webhook_id, "user_id": auth.id })
+        .await;
+    match result {
+        Ok(result) => {
+            if result.deleted_count == 0 {
+                return (
+                    StatusCode::NOT_FOUND,
+                    String::from("Could not find webhook"),
+                )
+                    .into_response();
+            }
+            StatusCode::OK.into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Error occurred while querying database");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr},
+};
+
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    Url,
+};
+
+use crate::config;
+
+/// Why webhooks can't be sent to a URL
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TargetError {
+    InvalidUrl,
+    Insecure,
+    Unresolvable,
+    NotPublic,
+}
+
+impl fmt::Display for TargetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TargetError::InvalidUrl => "Webhook URL must be a valid http or https URL",
+            TargetError::Insecure => "Webhook URL must use https",
+            TargetError::Unresolvable => "Could not find the webhook URL's host",
+            TargetError::NotPublic => "Webhook URL must point to a public address",
+        })
+    }
+}
+
+impl std::error::Error for TargetError {}
+
+/// Whether an address is on the public internet, rather than this machine or a private network
+#[must_use]
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            // 0.0.0.0/8 is "this network" and 100.64.0.0/10 is carrier-grade NAT
+            let special = first == 0 || (first == 100 && second & 0xc0 == 64);
+            !(special
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation())
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+/// Looks up a host, refusing it if any of its addresses aren't public
+async fn resolve_public(host: &str, port: u16) -> Result<Vec<SocketAddr>, TargetError> {
+    let addrs = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|_| TargetError::Unresolvable)?
+        .collect::<Vec<_>>();
+    if addrs.is_empty() {
+        return Err(TargetError::Unresolvable);
+    }
+    if !addrs.iter().all(|addr| is_public(addr.ip())) {
+        return Err(TargetError::NotPublic);
+    }
+    Ok(addrs)
+}
+
+/// Checks that webhooks may be sent to a URL. It must use https, unless this isn't production, and
+/// must not point at this machine or a private network.
+///
+/// # Errors
+/// Returns why the URL can't be used
+pub async fn check_target(url: &str) -> Result<Url, TargetError> {
+    let url = Url::parse(url).map_err(|_| TargetError::InvalidUrl)?;
+    match url.scheme() {
+        "https" => {}
+        "http" if config::get_is_production() != "PRODUCTION" => {}
+        "http" => return Err(TargetError::Insecure),
+        _ => return Err(TargetError::InvalidUrl),
+    }
+    let port = url.port_or_known_default().ok_or(TargetError::InvalidUrl)?;
+    let host = url.host_str().ok_or(TargetError::InvalidUrl)?;
+    // IPv6 hosts are written in square brackets
+    match host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+    {
+        Ok(ip) if !is_public(ip) => return Err(TargetError::NotPublic),
+        Ok(..) => {}
+        Err(..) => {
+            resolve_public(host, port).await?;
+        }
+    }
+    Ok(url)
+}
+
+/// Resolves hosts for webhook requests, so that a host can't be pointed somewhere private after
+/// its URL was checked
+#[derive(Debug)]
+pub struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs = resolve_public(name.as_str(), 0).await?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[test]
+fn private_addresses_are_not_public() {
+    for ip in [
+        "127.0.0.1",
+        "10.1.2.3",
+        "172.16.0.1",
+        "192.168.1.1",
+        "169.254.169.254",
+        "0.0.0.0",
+        "100.64.0.1",
+        "::1",
+        "::",
+        "fd00::1",
+        "fe80::1",
+        "::ffff:127.0.0.1",
+    ] {
+        assert!(!is_public(ip.parse().unwrap()), "{ip} should not be public");
+    }
+    for ip in ["93.184.215.14", "2606:2800:21f:cb07:6820:80da:af6b:8b2c"] {
+        assert!(is_public(ip.parse().unwrap()), "{ip} should be public");
+    }
+}
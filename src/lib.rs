@@ -5,3 +5,4 @@
 pub mod app;
 mod config;
 pub mod metrics;
+pub mod scheduler;
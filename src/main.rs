@@ -1,10 +1,21 @@
+use parkinsons_pulse_service::app::AppState;
+
 #[tokio::main]
 async fn main() {
+    dotenvy::dotenv().ok();
     parkinsons_pulse_service::metrics::logging::init();
     tracing::info!("Initialised tracing");
 
+    // Built once, as setting it up migrates data and must not run twice at the same time
+    let app_state = AppState::new().await.unwrap_or_else(|e| {
+        tracing::error!(error = %e, "Error occurred while creating app state");
+        panic!("Error occurred while creating app state");
+    });
+    tracing::info!("App state initialized");
+
     let _ = tokio::join!(
-        parkinsons_pulse_service::app::run(),
+        parkinsons_pulse_service::app::run(app_state.clone()),
         parkinsons_pulse_service::metrics::run(),
+        parkinsons_pulse_service::scheduler::run(app_state.db),
     );
 }
@@ -0,0 +1,51 @@
+use axum::async_trait;
+use mongodb::{
+    bson::{doc, to_document},
+    Database,
+};
+
+use super::NotificationChannel;
+use crate::app::{
+    events::{publish, LiveEvent},
+    models::Notification,
+};
+
+/// Stores notifications in the database for users to read in the app
+pub struct InboxChannel {
+    db: Database,
+}
+
+impl InboxChannel {
+    #[must_use]
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for InboxChannel {
+    fn name(&self) -> &'static str {
+        "inbox"
+    }
+
+    async fn deliver(&self, notification: &Notification) -> anyhow::Result<()> {
+        let result = self
+            .db
+            .collection::<Notification>("notifications")
+            .update_one(
+                doc! { "_id": notification.id },
+                doc! { "$setOnInsert": to_document(notification)? },
+            )
+            .upsert(true)
+            .await?;
+        if result.upserted_id.is_some() {
+            publish(
+                &self.db,
+                vec![notification.user_id],
+                LiveEvent::Notification(notification.clone()),
+            )
+            .await;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+pub mod inbox;
+pub mod webhook;
+
+use axum::async_trait;
+
+use crate::app::models::Notification;
+
+/// A way of getting a notification to a user
+#[async_trait]
+pub trait NotificationChannel: Send + Sync {
+    /// Name of the channel, used in logs
+    fn name(&self) -> &'static str;
+
+    /// Delivers a notification. Delivering the same notification twice should be safe, as failed
+    /// jobs are retried.
+    ///
+    /// # Errors
+    /// Returns an error if the notification could not be delivered
+    async fn deliver(&self, notification: &Notification) -> anyhow::Result<()>;
+}
+
+/// Sends notifications through every configured channel
+#[derive(Default)]
+pub struct Notifier {
+    channels: Vec<Box<dyn NotificationChannel>>,
+}
+
+impl Notifier {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_channel(&mut self, channel: impl NotificationChannel + 'static) {
+        self.channels.push(Box::new(channel));
+    }
+
+    /// Delivers a notification through every channel, even if some fail
+    ///
+    /// # Errors
+    /// Returns an error if any channel failed to deliver the notification
+    pub async fn notify(&self, notification: &Notification) -> anyhow::Result<()> {
+        let mut failed = Vec::new();
+        for channel in &self.channels {
+            if let Err(e) = channel.deliver(notification).await {
+                tracing::warn!(error = %e, channel = channel.name(), "Failed to deliver notification");
+                failed.push(channel.name());
+            }
+        }
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Failed to deliver notification through {}",
+                failed.join(", ")
+            ))
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use axum::async_trait;
+
+use super::NotificationChannel;
+use crate::app::models::Notification;
+
+const TIMEOUT_SECONDS: u64 = 10;
+
+/// Posts notifications as JSON to a URL, e.g. a push notification gateway
+pub struct WebhookChannel {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl WebhookChannel {
+    #[must_use]
+    pub fn new(url: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+        }
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for WebhookChannel {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn deliver(&self, notification: &Notification) -> anyhow::Result<()> {
+        self.client
+            .post(&self.url)
+            .timeout(Duration::from_secs(TIMEOUT_SECONDS))
+            .json(notification)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn posts_notifications_to_url() {
+    use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+    use mongodb::bson::oid::ObjectId;
+    use tokio::sync::mpsc;
+
+    let (sender, mut receiver) = mpsc::unbounded_channel::<serde_json::Value>();
+    let stub = Router::new()
+        .route(
+            "/ok",
+            post(
+                |State(sender): State<mpsc::UnboundedSender<serde_json::Value>>,
+                 Json(body): Json<serde_json::Value>| async move {
+                    sender.send(body).unwrap();
+                    StatusCode::NO_CONTENT
+                },
+            ),
+        )
+        .route("/broken", post(|| async { StatusCode::BAD_GATEWAY }))
+        .with_state(sender);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, stub).await.unwrap() });
+
+    let medication_id = ObjectId::new();
+    let notification = Notification::from(
+        ObjectId::new(),
+        crate::app::models::NotificationKind::DoseReminder { medication_id },
+        String::from("Time to take Sinemet"),
+        String::from("25/100mg (with food)"),
+    );
+
+    WebhookChannel::new(format!("http://{addr}/ok"))
+        .deliver(&notification)
+        .await
+        .unwrap();
+    let body = receiver.recv().await.unwrap();
+    assert_eq!(body["title"], "Time to take Sinemet");
+    assert_eq!(
+        body["kind"]["DoseReminder"]["medication_id"]["$oid"],
+        medication_id.to_hex()
+    );
+
+    assert!(WebhookChannel::new(format!("http://{addr}/broken"))
+        .deliver(&notification)
+        .await
+        .is_err());
+}
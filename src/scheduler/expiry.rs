@@ -0,0 +1,99 @@
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, DateTime},
+    Database,
+};
+
+use crate::app::{
+    models::{CaregiverGrant, Notification, NotificationKind, User},
+    notification::notify,
+};
+
+/// Removes caregiver grants whose time has run out, and tells both the patient and the caregiver
+///
+/// Returns the number of grants removed
+pub(super) async fn remove_expired_grants(
+    db: &Database,
+    now: chrono::DateTime<Utc>,
+) -> mongodb::error::Result<u64> {
+    let now = DateTime::from_chrono(now);
+    let users = db.collection::<User>("users");
+    let mut patients = users
+        .find(doc! { "caregivers.expires_at": { "$lte": now } })
+        .await?;
+
+    let mut removed = 0;
+    while let Some(patient) = patients.try_next().await? {
+        let Some(patient_id) = patient.id else {
+            continue;
+        };
+        for grant in patient
+            .caregivers
+            .iter()
+            .filter(|grant| !grant.is_active(now))
+        {
+            let result = users
+                .update_one(
+                    doc! { "_id": patient_id },
+                    doc! { "$pull": { "caregivers": {
+                        "caregiver_id": grant.caregiver_id,
+                        "expires_at": { "$lte": now }
+                    } } },
+                )
+                .await?;
+            // The patient may have given the caregiver more time since we looked
+            if result.modified_count == 0 {
+                continue;
+            }
+            removed += 1;
+            notify_expired(db, &patient, patient_id, grant).await?;
+        }
+    }
+    Ok(removed)
+}
+
+async fn notify_expired(
+    db: &Database,
+    patient: &User,
+    patient_id: mongodb::bson::oid::ObjectId,
+    grant: &CaregiverGrant,
+) -> mongodb::error::Result<()> {
+    let caregiver_name = db
+        .collection::<User>("users")
+        .find_one(doc! { "_id": grant.caregiver_id })
+        .await?
+        .map_or_else(
+            || String::from("A caregiver"),
+            |caregiver| format!("{} {}", caregiver.first_name, caregiver.last_name),
+        );
+    let kind = NotificationKind::CaregiverAccessExpired {
+        patient_id,
+        caregiver_id: grant.caregiver_id,
+    };
+
+    notify(
+        db,
+        Notification::from(
+            patient_id,
+            kind.clone(),
+            String::from("Caregiver access ended"),
+            format!("{caregiver_name}'s access to your information has expired"),
+        ),
+    )
+    .await;
+    notify(
+        db,
+        Notification::from(
+            grant.caregiver_id,
+            kind,
+            String::from("Caregiver access ended"),
+            format!(
+                "Your access to {} {}'s information has expired",
+                patient.first_name, patient.last_name
+            ),
+        ),
+    )
+    .await;
+    Ok(())
+}
@@ -0,0 +1,72 @@
+pub mod channels;
+mod expiry;
+mod planner;
+mod worker;
+
+use chrono::{Duration, Utc};
+use mongodb::{
+    bson::{doc, to_document},
+    Database,
+};
+
+use crate::{app::models::Job, config};
+use channels::{inbox::InboxChannel, webhook::WebhookChannel, Notifier};
+
+/// How often to look for upcoming reminders and store them as jobs
+const PLAN_EVERY_MINUTES: i64 = 15;
+
+/// Runs the scheduler
+///
+/// This periodically stores upcoming reminders as jobs in the database and removes caregiver
+/// access that has expired, then runs any jobs that are due. As jobs are stored, any that were due
+/// while the service was down still run once it is back up.
+pub async fn run(db: Database) {
+    let mut notifier = Notifier::new();
+    notifier.add_channel(InboxChannel::new(db.clone()));
+    if let Some(url) = config::get_reminder_webhook_url() {
+        tracing::info!(%url, "Sending reminders to webhook");
+        notifier.add_channel(WebhookChannel::new(url));
+    }
+
+    let mut interval = tokio::time::interval(config::get_scheduler_poll_interval());
+    let mut last_planned_at = None;
+    tracing::info!("Running scheduler");
+    loop {
+        interval.tick().await;
+
+        let now = Utc::now();
+        if last_planned_at.is_none_or(|last| now - last >= Duration::minutes(PLAN_EVERY_MINUTES)) {
+            match planner::plan_reminders(&db, now).await {
+                Ok(planned) => {
+                    tracing::debug!(planned, "Planned reminders");
+                    last_planned_at = Some(now);
+                }
+                Err(e) => tracing::error!(error = %e, "Failed to plan reminders"),
+            }
+            match expiry::remove_expired_grants(&db, now).await {
+                Ok(0) => {}
+                Ok(removed) => tracing::info!(removed, "Removed expired caregiver grants"),
+                Err(e) => tracing::error!(error = %e, "Failed to remove expired caregiver grants"),
+            }
+        }
+
+        worker::run_due_jobs(&db, &notifier).await;
+    }
+}
+
+/// Stores a job unless one with the same key already exists
+///
+/// Returns 1 if the job was stored, and 0 otherwise
+///
+/// # Errors
+/// Returns an error if the job could not be stored
+pub async fn insert_job(db: &Database, job: Job) -> mongodb::error::Result<u64> {
+    let key = job.key.clone();
+    let job = to_document(&job)?;
+    let result = db
+        .collection::<Job>("jobs")
+        .update_one(doc! { "key": key }, doc! { "$setOnInsert": job })
+        .upsert(true)
+        .await?;
+    Ok(u64::from(result.upserted_id.is_some()))
+}
@@ -0,0 +1,77 @@
+use chrono::{Duration, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Bson, DateTime},
+    Database,
+};
+
+use super::insert_job;
+use crate::app::models::{Form, Job, JobKind, MedicationTrackerEntry, OVERDUE_AFTER_HOURS};
+
+/// How far ahead reminders are stored as jobs
+const PLAN_AHEAD_HOURS: i64 = 24;
+
+/// Stores a job for every dose and form due in the next `PLAN_AHEAD_HOURS` hours, unless it has
+/// already been stored
+///
+/// Returns the number of new jobs
+pub(super) async fn plan_reminders(
+    db: &Database,
+    now: chrono::DateTime<Utc>,
+) -> mongodb::error::Result<u64> {
+    let until = now + Duration::hours(PLAN_AHEAD_HOURS);
+    let mut planned = 0;
+
+    let mut medications = db
+        .collection::<MedicationTrackerEntry>("medications")
+        .find(doc! { "stopped_at": Bson::Null, "schedule": { "$ne": Bson::Null } })
+        .await?;
+    while let Some(medication) = medications.try_next().await? {
+        let Some(schedule) = &medication.schedule else {
+            continue;
+        };
+        for due_at in schedule.doses_between(now, until) {
+            let job = Job::from(
+                format!("dose-reminder:{}:{}", medication.id, due_at.timestamp()),
+                JobKind::DoseReminder {
+                    medication_id: medication.id,
+                    due_at: DateTime::from_chrono(due_at),
+                },
+                DateTime::from_chrono(due_at),
+            );
+            planned += insert_job(db, job).await?;
+        }
+    }
+
+    let mut forms = db
+        .collection::<Form>("forms")
+        .find(doc! { "schedule": { "$ne": Bson::Null } })
+        .await?;
+    while let Some(form) = forms.try_next().await? {
+        let (Some(form_id), Some(schedule)) = (form.id, &form.schedule) else {
+            continue;
+        };
+        for due_at in schedule.due_between(now, until) {
+            let job = Job::from(
+                format!("form-due:{form_id}:{}", due_at.timestamp()),
+                JobKind::FormDue {
+                    form_id,
+                    due_at: DateTime::from_chrono(due_at),
+                },
+                DateTime::from_chrono(due_at),
+            );
+            planned += insert_job(db, job).await?;
+            let job = Job::from(
+                format!("form-overdue:{form_id}:{}", due_at.timestamp()),
+                JobKind::FormOverdue {
+                    form_id,
+                    due_at: DateTime::from_chrono(due_at),
+                },
+                DateTime::from_chrono(due_at + Duration::hours(OVERDUE_AFTER_HOURS)),
+            );
+            planned += insert_job(db, job).await?;
+        }
+    }
+
+    Ok(planned)
+}
@@ -0,0 +1,228 @@
+use chrono::{Duration, Utc};
+use mongodb::{
+    bson::{doc, oid::ObjectId, Bson, DateTime},
+    options::ReturnDocument,
+    Database,
+};
+
+use super::channels::Notifier;
+use crate::app::models::{
+    DeliveryStatus, Event, Form, Job, JobKind, JobStatus, MedicationTrackerEntry, Notification,
+    NotificationKind,
+};
+use crate::app::webhook::deliver::{attempt_delivery, set_delivery_status};
+
+/// How long a worker has to finish a job before another may claim it
+const LOCK_MINUTES: i64 = 5;
+
+/// Jobs this late are skipped, as a reminder hours after the fact does more harm than good
+const MAX_LATENESS_MINUTES: i64 = 60;
+
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Runs every job that is due, one at a time
+pub(super) async fn run_due_jobs(db: &Database, notifier: &Notifier) {
+    loop {
+        let job = match claim_job(db).await {
+            Ok(Some(job)) => job,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to claim job");
+                return;
+            }
+        };
+        let job_id = job.id;
+        let (status, error) = match run_job(db, notifier, &job).await {
+            Ok(status) => (status, None),
+            Err(e) => {
+                tracing::warn!(error = %e, %job_id, attempts = job.attempts, "Job failed");
+                let status = if job.attempts >= MAX_ATTEMPTS {
+                    JobStatus::Failed
+                } else {
+                    JobStatus::Pending
+                };
+                (status, Some(e.to_string()))
+            }
+        };
+        if let (JobStatus::Failed, JobKind::WebhookDelivery { delivery_id }) = (status, &job.kind) {
+            if let Err(e) = set_delivery_status(db, *delivery_id, DeliveryStatus::Failed).await {
+                tracing::error!(error = %e, %job_id, "Failed to update webhook delivery");
+            }
+        }
+        if let Err(e) = finish_job(db, &job, status, error).await {
+            tracing::error!(error = %e, %job_id, "Failed to update job");
+        }
+    }
+}
+
+/// Locks the job that has been due the longest so no other worker runs it
+async fn claim_job(db: &Database) -> mongodb::error::Result<Option<Job>> {
+    let now = Utc::now();
+    db.collection::<Job>("jobs")
+        .find_one_and_update(
+            doc! {
+              "status": "Pending",
+              "run_at": { "$lte": DateTime::from_chrono(now) },
+              "$or": [
+                { "locked_until": Bson::Null },
+                { "locked_until": { "$lt": DateTime::from_chrono(now) } }
+              ]
+            },
+            doc! {
+              "$set": { "locked_until": DateTime::from_chrono(now + Duration::minutes(LOCK_MINUTES)) },
+              "$inc": { "attempts": 1 }
+            },
+        )
+        .sort(doc! { "run_at": 1 })
+        .return_document(ReturnDocument::After)
+        .await
+}
+
+async fn finish_job(
+    db: &Database,
+    job: &Job,
+    status: JobStatus,
+    error: Option<String>,
+) -> mongodb::error::Result<()> {
+    let now = Utc::now();
+    let update = if status == JobStatus::Pending {
+        // Back off exponentially before trying again
+        let delay = Duration::minutes(1 << job.attempts.min(10));
+        doc! { "$set": {
+          "run_at": DateTime::from_chrono(now + delay),
+          "locked_until": Bson::Null,
+          "last_error": error,
+        } }
+    } else {
+        doc! { "$set": {
+          "status": mongodb::bson::to_bson(&status)?,
+          "locked_until": Bson::Null,
+          "last_error": error,
+          "finished_at": DateTime::from_chrono(now),
+        } }
+    };
+    db.collection::<Job>("jobs")
+        .update_one(doc! { "_id": job.id }, update)
+        .await?;
+    Ok(())
+}
+
+async fn run_job(db: &Database, notifier: &Notifier, job: &Job) -> anyhow::Result<JobStatus> {
+    if let JobKind::WebhookDelivery { delivery_id } = job.kind {
+        attempt_delivery(db, delivery_id).await?;
+        return Ok(JobStatus::Done);
+    }
+    let Some(notification) = build_notification(db, job).await? else {
+        return Ok(JobStatus::Skipped);
+    };
+    notifier.notify(&notification).await?;
+    Ok(JobStatus::Done)
+}
+
+/// Works out what to tell the user, or `None` if the reminder is no longer needed
+async fn build_notification(db: &Database, job: &Job) -> anyhow::Result<Option<Notification>> {
+    let now = Utc::now();
+    match &job.kind {
+        JobKind::DoseReminder {
+            medication_id,
+            due_at,
+        } => {
+            if now - due_at.to_chrono() > Duration::minutes(MAX_LATENESS_MINUTES) {
+                return Ok(None);
+            }
+            let Some(medication) = db
+                .collection::<MedicationTrackerEntry>("medications")
+                .find_one(doc! { "_id": medication_id })
+                .await?
+            else {
+                return Ok(None);
+            };
+            // The medication may have been stopped or rescheduled since the job was stored
+            let still_due = medication.stopped_at.is_none()
+                && medication.schedule.as_ref().is_some_and(|schedule| {
+                    schedule
+                        .doses_between(due_at.to_chrono(), due_at.to_chrono())
+                        .contains(&due_at.to_chrono())
+                });
+            if !still_due {
+                return Ok(None);
+            }
+            Ok(Some(Notification {
+                // Reuse the job's ID so retrying a job never creates a second notification
+                id: job.id,
+                ..Notification::from(
+                    medication.user_id,
+                    NotificationKind::DoseReminder {
+                        medication_id: medication.id,
+                    },
+                    format!("Time to take {}", medication.medication_name),
+                    format!("{} ({})", medication.dose, medication.timing),
+                )
+            }))
+        }
+        JobKind::FormDue { form_id, due_at } => {
+            if now - due_at.to_chrono() > Duration::minutes(MAX_LATENESS_MINUTES) {
+                return Ok(None);
+            }
+            let Some((user_id, form)) = find_scheduled_form(db, *form_id, *due_at).await? else {
+                return Ok(None);
+            };
+            Ok(Some(Notification {
+                id: job.id,
+                ..Notification::from(
+                    user_id,
+                    NotificationKind::FormDue { form_id: *form_id },
+                    format!("{} is due", form.title),
+                    String::from("Please fill in your form"),
+                )
+            }))
+        }
+        JobKind::FormOverdue { form_id, due_at } => {
+            let Some((user_id, form)) = find_scheduled_form(db, *form_id, *due_at).await? else {
+                return Ok(None);
+            };
+            let submitted = form.events.iter().any(|event| {
+                matches!(event, Event::FormSubmitted(submitted) if submitted.submitted_at >= *due_at)
+            });
+            if submitted {
+                return Ok(None);
+            }
+            Ok(Some(Notification {
+                id: job.id,
+                ..Notification::from(
+                    user_id,
+                    NotificationKind::FormOverdue { form_id: *form_id },
+                    format!("{} is overdue", form.title),
+                    String::from("You haven't filled in this form since it was due"),
+                )
+            }))
+        }
+        // These are sent straight to the webhook rather than as a notification
+        JobKind::WebhookDelivery { .. } => Ok(None),
+    }
+}
+
+/// Finds a form and the user it belongs to, if it is still scheduled to be due at `due_at`
+async fn find_scheduled_form(
+    db: &Database,
+    form_id: ObjectId,
+    due_at: DateTime,
+) -> mongodb::error::Result<Option<(ObjectId, Form)>> {
+    let Some(form) = db
+        .collection::<Form>("forms")
+        .find_one(doc! { "_id": form_id })
+        .await?
+    else {
+        return Ok(None);
+    };
+    // The form may have been rescheduled since the job was stored
+    let still_due = form.schedule.as_ref().is_some_and(|schedule| {
+        schedule
+            .due_between(due_at.to_chrono(), due_at.to_chrono())
+            .contains(&due_at.to_chrono())
+    });
+    Ok(form
+        .user_id
+        .filter(|_| still_due)
+        .map(|user_id| (user_id, form)))
+}